@@ -0,0 +1,279 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Expr, ExprLit, Fields, GenericParam,
+    Ident, ItemFn, Lit, Type,
+};
+
+// Methods and associated functions of f64 which have a counterpart in autocrab::functions.
+fn function_name(method: &str) -> Option<&'static str>
+{
+    match method {
+        "sin" => Some("sin"),
+        "cos" => Some("cos"),
+        "tan" => Some("tan"),
+        "ln" => Some("ln"),
+        "exp" => Some("exp"),
+        "sqrt" => Some("sqrt"),
+        "abs" => Some("abs"),
+        "powf" | "powi" => Some("pow"),
+        _ => None,
+    }
+}
+
+fn is_f64(ty: &Type) -> bool
+{
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("f64"))
+}
+
+fn is_float_literal(expr: &Expr) -> bool
+{
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Float(_), ..
+        }) => true,
+        Expr::Unary(unary) => is_float_literal(&unary.expr),
+        Expr::Paren(paren) => is_float_literal(&paren.expr),
+        _ => false,
+    }
+}
+
+fn unparenthesized(expr: &Expr) -> &Expr
+{
+    match expr {
+        Expr::Paren(paren) => unparenthesized(&paren.expr),
+        _ => expr,
+    }
+}
+
+fn constant(expr: &Expr) -> Expr
+{
+    parse_quote!(::autocrab::variable::Variable::with_derivative(#expr as f64, 0.0))
+}
+
+struct Differentiable;
+
+impl VisitMut for Differentiable
+{
+    fn visit_type_mut(&mut self, ty: &mut Type)
+    {
+        if is_f64(ty) {
+            *ty = parse_quote!(::autocrab::variable::Variable);
+            return;
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    // Casts like `i as f64` still produce plain numbers.
+    fn visit_expr_cast_mut(&mut self, cast: &mut syn::ExprCast)
+    {
+        self.visit_expr_mut(&mut cast.expr);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr)
+    {
+        visit_mut::visit_expr_mut(self, expr);
+
+        let replacement: Option<Expr> = match expr {
+            Expr::MethodCall(call) => function_name(&call.method.to_string()).map(|name| {
+                let function = format_ident!("{}", name);
+                let receiver = unparenthesized(&call.receiver);
+                let args = call.args.iter();
+                if call.method == "powi" {
+                    quote!(::autocrab::functions::#function(#receiver, (#(#args)*) as f64))
+                } else {
+                    quote!(::autocrab::functions::#function(#receiver #(, #args)*))
+                }
+            }),
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path)
+                    if path.path.segments.len() == 2 && path.path.segments[0].ident == "f64" =>
+                {
+                    let method = path.path.segments[1].ident.to_string();
+                    function_name(&method).map(|name| {
+                        let function = format_ident!("{}", name);
+                        let args = call.args.iter();
+                        if method == "powi" {
+                            let mut args = call.args.iter();
+                            let base = args.next();
+                            let exponent = args.next();
+                            quote!(::autocrab::functions::#function(#base, (#exponent) as f64))
+                        } else {
+                            quote!(::autocrab::functions::#function(#(#args),*))
+                        }
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+        .map(|tokens| parse_quote!(#tokens));
+
+        if let Some(replacement) = replacement {
+            *expr = replacement;
+        }
+    }
+
+    // Bare literals starting a computation, like `let mut sum = 0.0;` or `[0.0; N]`, have to
+    // become constant variables. Literals used as operands are left alone since Variable
+    // implements arithmetic with f64.
+    fn visit_local_mut(&mut self, local: &mut syn::Local)
+    {
+        visit_mut::visit_local_mut(self, local);
+        if let Some(init) = &mut local.init {
+            if is_float_literal(&init.expr) {
+                *init.expr = constant(&init.expr);
+            } else if let Expr::Repeat(repeat) = &mut *init.expr {
+                if is_float_literal(&repeat.expr) {
+                    *repeat.expr = constant(&repeat.expr);
+                }
+            }
+        }
+    }
+}
+
+// Generates a copy of a function written over f64 where every f64 is replaced by Variable and
+// the f64 math methods (`x.sin()`, `x.powf(p)`, `f64::exp(x)`, ...) are mapped onto
+// autocrab::functions. The original function is kept, the copy is named `<name>_variable`
+// unless a name is given, e.g. `#[differentiable(objective)]`.
+//
+// The body can use arithmetic, the mapped methods, compound assignment and literals. Comparisons
+// between values and other f64 methods are not supported.
+#[proc_macro_attribute]
+pub fn differentiable(attribute: TokenStream, item: TokenStream) -> TokenStream
+{
+    let original = parse_macro_input!(item as ItemFn);
+    let name = if attribute.is_empty() {
+        format_ident!("{}_variable", original.sig.ident)
+    } else {
+        parse_macro_input!(attribute as Ident)
+    };
+
+    let mut differentiable = original.clone();
+    differentiable.sig.ident = name;
+    Differentiable.visit_item_fn_mut(&mut differentiable);
+
+    quote!(
+        #original
+
+        #differentiable
+    )
+    .into()
+}
+
+// Implements autocrab::structs::Differentiable for a struct generic over its scalar type, like
+// `struct Pose<T> { x: T, y: T, theta: T }`. Fields can be the scalar itself, arrays, and other
+// Differentiable structs over the same scalar, they are flattened in declaration order.
+#[proc_macro_derive(Differentiable)]
+pub fn derive_differentiable(item: TokenStream) -> TokenStream
+{
+    let input = parse_macro_input!(item as DeriveInput);
+    match differentiable_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn differentiable_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream>
+{
+    let name = &input.ident;
+    let mut type_parameters = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            _ => None,
+        });
+    let scalar = match (type_parameters.next(), type_parameters.next()) {
+        (Some(scalar), None) if input.generics.params.len() == 1 => scalar,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Differentiable needs exactly one type parameter, the scalar type",
+            ))
+        }
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Differentiable can only be derived for structs",
+            ))
+        }
+    };
+    let members: Vec<syn::Member> = fields.members().collect();
+    let types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+    let labels: Vec<String> = members
+        .iter()
+        .map(|member| match member {
+            syn::Member::Named(ident) => ident.to_string(),
+            syn::Member::Unnamed(index) => index.index.to_string(),
+        })
+        .collect();
+
+    let trait_path = quote!(::autocrab::structs::Differentiable);
+    let element_path = quote!(::autocrab::structs::Element);
+    let other = format_ident!("__AutocrabScalar");
+
+    let construct = match fields {
+        Fields::Named(_) => quote!(Self { #(#members: {
+            let size = <#types as #trait_path>::SIZE;
+            let field = <#types as #trait_path>::read_values(&values[offset..offset + size]);
+            offset += size;
+            field
+        }),* }),
+        Fields::Unnamed(_) => quote!(Self(#({
+            let size = <#types as #trait_path>::SIZE;
+            let field = <#types as #trait_path>::read_values(&values[offset..offset + size]);
+            offset += size;
+            field
+        }),*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    Ok(quote!(
+        impl<#scalar: #element_path> #trait_path for #name<#scalar>
+        {
+            type Scalar = #scalar;
+            type With<#other: #element_path> = #name<#other>;
+
+            const SIZE: usize = 0 #(+ <#types as #trait_path>::SIZE)*;
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            fn write_values(&self, values: &mut [#scalar])
+            {
+                let mut offset = 0;
+                #(
+                    let size = <#types as #trait_path>::SIZE;
+                    <#types as #trait_path>::write_values(
+                        &self.#members,
+                        &mut values[offset..offset + size],
+                    );
+                    offset += size;
+                )*
+            }
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            fn read_values(values: &[#scalar]) -> Self
+            {
+                let mut offset = 0;
+                #construct
+            }
+
+            #[allow(unused_variables)]
+            fn field_names(prefix: &str, names: &mut ::std::vec::Vec<::std::string::String>)
+            {
+                #(
+                    <#types as #trait_path>::field_names(
+                        &::autocrab::structs::field_name(prefix, #labels),
+                        names,
+                    );
+                )*
+            }
+        }
+    ))
+}
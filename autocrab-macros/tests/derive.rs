@@ -0,0 +1,73 @@
+use autocrab::almost_eq;
+use autocrab::functions::*;
+use autocrab::structs::*;
+use autocrab::variable::Variable;
+use autocrab_macros::Differentiable;
+
+#[derive(Differentiable, Debug, Copy, Clone, PartialEq)]
+struct Pose<T>
+{
+    x: T,
+    y: T,
+    theta: T,
+}
+
+#[derive(Differentiable, Debug, Copy, Clone, PartialEq)]
+struct Robot<T>
+{
+    pose: Pose<T>,
+    wheels: [T; 2],
+}
+
+#[derive(Differentiable, Debug, Copy, Clone, PartialEq)]
+struct Heading<T>(T, T);
+
+fn heading(robot: Robot<Variable>) -> Heading<Variable>
+{
+    let speed = (robot.wheels[0] + robot.wheels[1]) * 0.5;
+    Heading(
+        robot.pose.x + speed * cos(robot.pose.theta),
+        robot.pose.y + speed * sin(robot.pose.theta),
+    )
+}
+
+fn robot() -> Robot<f64>
+{
+    Robot {
+        pose: Pose {
+            x: 1.0,
+            y: 2.0,
+            theta: 0.5,
+        },
+        wheels: [3.0, 5.0],
+    }
+}
+
+#[test]
+fn test_layout()
+{
+    assert_eq!(Robot::<f64>::SIZE, 5);
+    assert_eq!(
+        Robot::<f64>::names(),
+        ["pose.x", "pose.y", "pose.theta", "wheels[0]", "wheels[1]"]
+    );
+    assert_eq!(Heading::<f64>::names(), ["0", "1"]);
+
+    let values: [f64; 5] = robot().to_array();
+    assert_eq!(values, [1.0, 2.0, 0.5, 3.0, 5.0]);
+    assert_eq!(Robot::from_array(values), robot());
+}
+
+#[test]
+fn test_evaluate_struct()
+{
+    let (value, jacobian) = evaluate_struct(heading, &robot());
+    assert!(almost_eq(value.0, 1.0 + 4.0 * 0.5f64.cos()));
+    assert!(almost_eq(value.1, 2.0 + 4.0 * 0.5f64.sin()));
+
+    assert_eq!(jacobian.output_names, ["0", "1"]);
+    assert_eq!(jacobian["0"].pose.x, 1.0);
+    assert_eq!(jacobian["0"].pose.y, 0.0);
+    assert!(almost_eq(jacobian["0"].pose.theta, -4.0 * 0.5f64.sin()));
+    assert!(almost_eq(jacobian["1"].wheels[1], 0.5 * 0.5f64.sin()));
+}
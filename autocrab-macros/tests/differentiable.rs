@@ -0,0 +1,118 @@
+use autocrab::almost_eq;
+use autocrab::evaluate::evaluate;
+use autocrab_macros::differentiable;
+
+#[differentiable]
+fn objective_function(variables: [f64; 2]) -> [f64; 3]
+{
+    [
+        variables[0].sin() + variables[1].powi(2),
+        variables[0] * f64::exp(variables[1]),
+        (variables[0] * variables[1]).cos(),
+    ]
+}
+
+#[differentiable(polynomial_variable)]
+fn polynomial(variables: [f64; 1]) -> [f64; 1]
+{
+    let coefficients = [1.0, -2.0, 3.0];
+    let mut sum = 0.0;
+    let mut power: f64 = 1.0;
+    for (i, &coefficient) in coefficients.iter().enumerate() {
+        sum += coefficient * power * (i as f64 + 1.0);
+        power *= variables[0];
+    }
+    [sum.sqrt() + variables[0].powf(1.5) / variables[0].ln()]
+}
+
+#[differentiable]
+fn distance(variables: [f64; 2]) -> [f64; 1]
+{
+    [(variables[0] - variables[1]).abs()]
+}
+
+#[differentiable]
+fn reciprocal(variables: [f64; 2]) -> [f64; 1]
+{
+    [1.0 / variables[0] + variables[1]]
+}
+
+// Integer literals stay integers, n is a loop bound.
+#[differentiable]
+fn repeated_square_root(variables: [f64; 1]) -> [f64; 1]
+{
+    let n = 3;
+    let mut root = variables[0];
+    for _ in 0..n {
+        root = root.sqrt();
+    }
+    [root]
+}
+
+#[test]
+fn test_original_function_is_kept()
+{
+    let values = objective_function([1.0, 2.0]);
+    assert!(almost_eq(values[0], f64::sin(1.0) + 4.0));
+}
+
+#[test]
+fn test_generated_objective()
+{
+    let (values, jacobian) = evaluate(objective_function_variable, [1.0, 2.0]);
+    assert!(almost_eq(values[0], f64::sin(1.0) + 4.0));
+    assert!(almost_eq(values[1], f64::exp(2.0)));
+    assert!(almost_eq(values[2], f64::cos(2.0)));
+
+    assert!(almost_eq(jacobian[0][0], f64::cos(1.0)));
+    assert!(almost_eq(jacobian[0][1], 4.0));
+    assert!(almost_eq(jacobian[1][0], f64::exp(2.0)));
+    assert!(almost_eq(jacobian[1][1], f64::exp(2.0)));
+    assert!(almost_eq(jacobian[2][0], -2.0 * f64::sin(2.0)));
+    assert!(almost_eq(jacobian[2][1], -f64::sin(2.0)));
+}
+
+#[test]
+fn test_loops_and_literals()
+{
+    let x = 2.0;
+    let (values, jacobian) = evaluate(polynomial_variable, [x]);
+    assert!(almost_eq(values[0], polynomial([x])[0]));
+
+    let step = 1.0e-6;
+    let forward = polynomial([x + step])[0];
+    let backward = polynomial([x - step])[0];
+    assert!((jacobian[0][0] - (forward - backward) / (2.0 * step)).abs() < 1.0e-6);
+}
+
+#[test]
+fn test_abs()
+{
+    let (values, jacobian) = evaluate(distance_variable, [1.0, 3.0]);
+    assert!(almost_eq(values[0], distance([1.0, 3.0])[0]));
+    assert!(almost_eq(jacobian[0][0], -1.0));
+    assert!(almost_eq(jacobian[0][1], 1.0));
+
+    // The derivative at the kink is 0, as for functions::abs.
+    let (_, jacobian) = evaluate(distance_variable, [2.0, 2.0]);
+    assert_eq!(jacobian[0], [0.0, 0.0]);
+}
+
+#[test]
+fn test_literal_numerator()
+{
+    let (values, jacobian) = evaluate(reciprocal_variable, [2.0, 3.0]);
+    assert!(almost_eq(values[0], reciprocal([2.0, 3.0])[0]));
+    assert!(almost_eq(jacobian[0][0], -0.25));
+    assert!(almost_eq(jacobian[0][1], 1.0));
+}
+
+#[test]
+fn test_integer_loop_bound()
+{
+    let (values, jacobian) = evaluate(repeated_square_root_variable, [256.0]);
+    assert!(almost_eq(values[0], repeated_square_root([256.0])[0]));
+    assert!(almost_eq(values[0], 2.0));
+    // d/dx x^(1/8) = x^(-7/8) / 8
+    assert!(almost_eq(jacobian[0][0], 256f64.powf(-0.875) / 8.0));
+}
@@ -0,0 +1,55 @@
+use autocrab::backend::*;
+use autocrab::evaluate::Objective;
+use autocrab::scalar::Scalar;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+struct Rosenbrock;
+
+impl Objective<2, 1> for Rosenbrock
+{
+    fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 1]
+    {
+        let x = variables[0];
+        let y = variables[1];
+        let a = T::constant(1.0) - x;
+        let b = y - x * x;
+        [a * a + T::constant(100.0) * b * b + (x * y).sin().exp()]
+    }
+}
+
+fn main()
+{
+    let x = [0.3, -1.2];
+    let iterations = 100_000;
+    let (_, reference) = evaluate_with(&Rosenbrock, x, Backend::Dual);
+
+    let backends = [
+        ("dual numbers", Backend::Dual),
+        ("complex step", Backend::complex_step()),
+        ("finite differences", Backend::finite_difference()),
+    ];
+
+    for (name, backend) in backends {
+        let start = Instant::now();
+        let mut jacobian = [[0.0; 2]; 1];
+        for _ in 0..iterations {
+            (_, jacobian) = evaluate_with(&Rosenbrock, black_box(x), backend);
+        }
+        let elapsed = start.elapsed();
+
+        let error = jacobian[0]
+            .iter()
+            .zip(reference[0])
+            .map(|(derivative, expected)| (derivative - expected).abs())
+            .fold(0.0, f64::max);
+        println!(
+            "{:>20}: gradient {:?}, max deviation from dual {:e}, {:?} per evaluation",
+            name,
+            jacobian[0],
+            error,
+            elapsed / iterations
+        );
+    }
+}
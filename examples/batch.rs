@@ -0,0 +1,61 @@
+use autocrab::backend::*;
+use autocrab::batch::*;
+use autocrab::evaluate::Objective;
+use autocrab::scalar::Scalar;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+// p(z) = z^16 + z^15 + ... + 1 by Horner's scheme, split into real and imaginary parts. Only
+// arithmetic, which is where the lanes pay off.
+struct Polynomial;
+
+impl Objective<2, 2> for Polynomial
+{
+    fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+    {
+        let x = variables[0];
+        let y = variables[1];
+        let one = T::constant(1.0);
+        let mut re = one;
+        let mut im = T::constant(0.0);
+        for _ in 0..16 {
+            (re, im) = (re * x - im * y + one, re * y + im * x);
+        }
+        [re, im]
+    }
+}
+
+fn main()
+{
+    let rows = 360;
+    let cols = 640;
+    let points: Vec<[f64; 2]> = (0..rows)
+        .flat_map(|r| {
+            (0..cols).map(move |c| {
+                [
+                    -2.0 + 4.0 * c as f64 / cols as f64,
+                    -2.0 + 4.0 * r as f64 / rows as f64,
+                ]
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+    let scalar: Vec<_> = points
+        .iter()
+        .map(|&x| evaluate_with(&Polynomial, black_box(x), Backend::Dual))
+        .collect();
+    println!("{:>8}: {:?}", "scalar", start.elapsed());
+
+    let start = Instant::now();
+    let lanes4 = evaluate_batch_with_lanes::<4, 2, 2>(&Polynomial, black_box(&points));
+    println!("{:>8}: {:?}", "4 lanes", start.elapsed());
+
+    let start = Instant::now();
+    let lanes8 = evaluate_batch_with_lanes::<8, 2, 2>(&Polynomial, black_box(&points));
+    println!("{:>8}: {:?}", "8 lanes", start.elapsed());
+
+    assert_eq!(scalar, lanes4);
+    assert_eq!(scalar, lanes8);
+}
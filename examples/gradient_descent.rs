@@ -1,6 +1,7 @@
 use autocrab::evaluate::evaluate;
 use autocrab::evaluate::ObjectiveFunction;
 use autocrab::functions::*;
+use autocrab::table::JacobianTable;
 use autocrab::variable::Variable;
 
 fn objective_function(variables: [Variable; 1]) -> [Variable; 1]
@@ -36,4 +37,12 @@ fn main()
 
     println!("Predicted minimum: {:?}", x[0]);
     println!("Actual minimum: {:?}", -1.0);
+
+    let result = evaluate(objective_function, x);
+    println!(
+        "{:.6}",
+        JacobianTable::evaluation(&result)
+            .with_input_labels(["x"])
+            .with_output_labels(["f"])
+    );
 }
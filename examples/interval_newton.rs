@@ -0,0 +1,43 @@
+use autocrab::evaluate::Objective;
+use autocrab::interval::{isolate_roots, Interval};
+use autocrab::scalar::Scalar;
+
+// The system from newton_fractals.rs, whose roots are the cube roots of unity. Instead of
+// following Newton iterations from a grid of starting points, every root in the box is enclosed
+// with a proof that it is unique.
+struct CubeRoots;
+
+impl Objective<2, 2> for CubeRoots
+{
+    fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+    {
+        let x = variables[0];
+        let y = variables[1];
+        let three = T::constant(3.0);
+        [
+            x.powf(3.0) - three * x * y.powf(2.0) - T::constant(1.0),
+            three * x.powf(2.0) * y - y.powf(3.0),
+        ]
+    }
+}
+
+fn main()
+{
+    let domain = [Interval::new(-2.0, 2.0), Interval::new(-2.0, 2.0)];
+    let roots = isolate_roots(&CubeRoots, domain, 1e-12);
+
+    println!(
+        "{} roots in {:.1} x {:.1}",
+        roots.len(),
+        domain[0],
+        domain[1]
+    );
+    for root in roots {
+        println!(
+            "x in {:.15}, y in {:.15}{}",
+            root.bounds[0],
+            root.bounds[1],
+            if root.unique { ", unique" } else { "" }
+        );
+    }
+}
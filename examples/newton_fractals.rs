@@ -1,5 +1,6 @@
-use autocrab::evaluate::evaluate;
+use autocrab::evaluate::evaluate_matrix;
 use autocrab::functions::*;
+use autocrab::linalg::Vector;
 use autocrab::variable::Variable;
 
 use std::io::{self, Write};
@@ -13,66 +14,24 @@ fn objective_function(variables: [Variable; 2]) -> [Variable; 2]
     [x_out, y_out]
 }
 
-fn negate(vector: [f64; 2]) -> [f64; 2]
-{
-    [-vector[0], -vector[1]]
-}
-
-fn iadd(dst: &mut [f64; 2], src: [f64; 2])
-{
-    dst[0] += src[0];
-    dst[1] += src[1];
-}
-
-fn sub(a: &[f64; 2], b: &[f64; 2]) -> [f64; 2]
-{
-    [a[0] - b[0], a[1] - b[1]]
-}
-
-fn norm_squared(v: [f64; 2]) -> f64
-{
-    let x = v[0];
-    let y = v[1];
-    x * x + y * y
-}
-
-fn solve(matrix: [[f64; 2]; 2], vector: [f64; 2]) -> Option<[f64; 2]>
-{
-    let a = matrix[0][0];
-    let b = matrix[0][1];
-    let c = matrix[1][0];
-    let d = matrix[1][1];
-
-    let det = a * d - b * c;
-    if det.abs() < 1e-8 {
-        return None;
-    }
-
-    let e = vector[0];
-    let f = vector[1];
-    let x1 = (e * d - b * f) / det;
-    let x2 = (a * f - e * c) / det;
-    Some([x1, x2])
-}
-
 fn lerp(a: f64, b: f64, t: f64) -> f64
 {
     a + (b - a) * t
 }
 
-fn find_root(max_iter: usize, start: [f64; 2], roots: &[[f64; 2]; 3], tol: f64) -> Option<usize>
+fn find_root(max_iter: usize, start: Vector<2>, roots: &[Vector<2>; 3], tol: f64) -> Option<usize>
 {
     let mut x = start;
     for _ in 0..max_iter {
-        let (current_values, current_jacobian) = evaluate(objective_function, x);
-        let neg = negate(current_values);
-
-        let res = solve(current_jacobian, neg)?;
+        let (current_values, current_jacobian) = evaluate_matrix(objective_function, x);
+        if current_jacobian.determinant().abs() < 1e-8 {
+            return None;
+        }
 
-        iadd(&mut x, res);
+        x = x + current_jacobian.solve(-current_values)?;
 
         for (k, root) in roots.iter().enumerate() {
-            if norm_squared(sub(&x, root)) < tol {
+            if (x - *root).norm_squared() < tol {
                 return Some(k);
             }
         }
@@ -83,9 +42,9 @@ fn find_root(max_iter: usize, start: [f64; 2], roots: &[[f64; 2]; 3], tol: f64)
 fn main()
 {
     let roots = [
-        [1.0, 0.0],
-        [-0.5, 0.5 * f64::sqrt(3.0)],
-        [-0.5, -0.5 * f64::sqrt(3.0)],
+        Vector([1.0, 0.0]),
+        Vector([-0.5, 0.5 * f64::sqrt(3.0)]),
+        Vector([-0.5, -0.5 * f64::sqrt(3.0)]),
     ];
 
     let colors: [[u8; 3]; 3] = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
@@ -110,7 +69,7 @@ fn main()
         let y = lerp(top, bottom, r as f64 / rows as f64);
         for c in 0..cols {
             let x = lerp(left, right, c as f64 / cols as f64);
-            match find_root(max_iter, [x, y], &roots, tol) {
+            match find_root(max_iter, Vector([x, y]), &roots, tol) {
                 Some(k) => {
                     handle.write_all(&colors[k]).unwrap();
                 }
@@ -0,0 +1,27 @@
+use autocrab::evaluate::Objective;
+use autocrab::expression::trace;
+use autocrab::scalar::Scalar;
+
+struct Pendulum;
+
+impl Objective<2, 2> for Pendulum
+{
+    fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+    {
+        let angle = variables[0];
+        let length = variables[1];
+        let gravity = T::constant(9.81);
+        [
+            length * angle.sin(),
+            (gravity / length).sqrt() * angle.cos(),
+        ]
+    }
+}
+
+fn main()
+{
+    let graph = trace(&Pendulum, [0.3, 2.0]);
+    println!("Objective:\n{}", graph);
+    println!("Derivative with respect to x0:\n{}", graph.derivative(0));
+    println!("Derivative with respect to x1:\n{}", graph.derivative(1));
+}
@@ -0,0 +1,112 @@
+use super::complex::*;
+use super::evaluate::*;
+use super::scalar::*;
+use super::variable::*;
+
+#[derive(Debug, Copy, Clone)]
+pub enum Backend
+{
+    Dual,
+    ComplexStep
+    {
+        step: f64,
+    },
+    FiniteDifference
+    {
+        step: f64,
+    },
+}
+
+impl Backend
+{
+    pub fn complex_step() -> Self
+    {
+        Backend::ComplexStep { step: 1.0e-20 }
+    }
+
+    pub fn finite_difference() -> Self
+    {
+        Backend::FiniteDifference { step: 1.0e-6 }
+    }
+}
+
+fn evaluate_dual<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+) -> ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT])
+{
+    let mut values = [0.0; OUTPUT_COUNT];
+    let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
+
+    for input_index in 0..VARIABLE_COUNT {
+        let mut variables = x.map(Variable::constant);
+        variables[input_index].derivative = 1.0;
+        let res = objective.call(variables);
+        for (output_index, output) in res.into_iter().enumerate() {
+            values[output_index] = output.value;
+            jacobian[output_index][input_index] = output.derivative;
+        }
+    }
+    (values, jacobian)
+}
+
+// Perturbs one input along the imaginary axis, f(x + ih) = f(x) + ih f'(x) + O(h^2). There is no
+// subtractive cancellation, so the step can be tiny and the derivative is exact to machine
+// precision for real analytic objectives.
+fn evaluate_complex_step<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+    step: f64,
+) -> ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT])
+{
+    let mut values = [0.0; OUTPUT_COUNT];
+    let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
+
+    for input_index in 0..VARIABLE_COUNT {
+        let mut variables = x.map(Complex::from);
+        variables[input_index].im = step;
+        let res = objective.call(variables);
+        for (output_index, output) in res.into_iter().enumerate() {
+            values[output_index] = output.re;
+            jacobian[output_index][input_index] = output.im / step;
+        }
+    }
+    (values, jacobian)
+}
+
+fn evaluate_finite_difference<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+    step: f64,
+) -> ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT])
+{
+    let values = objective.call(x);
+    let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
+
+    for input_index in 0..VARIABLE_COUNT {
+        let mut forward = x;
+        let mut backward = x;
+        forward[input_index] += step;
+        backward[input_index] -= step;
+        let forward = objective.call(forward);
+        let backward = objective.call(backward);
+        for output_index in 0..OUTPUT_COUNT {
+            jacobian[output_index][input_index] =
+                (forward[output_index] - backward[output_index]) / (2.0 * step);
+        }
+    }
+    (values, jacobian)
+}
+
+pub fn evaluate_with<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+    backend: Backend,
+) -> ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT])
+{
+    match backend {
+        Backend::Dual => evaluate_dual(objective, x),
+        Backend::ComplexStep { step } => evaluate_complex_step(objective, x, step),
+        Backend::FiniteDifference { step } => evaluate_finite_difference(objective, x, step),
+    }
+}
@@ -0,0 +1,259 @@
+use std::array;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::custom::{ChainRule, CustomFn};
+use super::evaluate::*;
+use super::math;
+use super::scalar::*;
+use super::variable::{chain, power};
+
+pub const DEFAULT_LANES: usize = 4;
+
+// Struct of arrays dual number, lane i holds the value and derivative at the i-th point of a
+// batch. Every operation is a plain loop over the lanes, which the compiler turns into SIMD
+// instructions for the arithmetic.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Lanes<const LANES: usize>
+{
+    pub values: [f64; LANES],
+    pub derivatives: [f64; LANES],
+}
+
+impl<const LANES: usize> Lanes<LANES>
+{
+    pub fn new(values: [f64; LANES], derivatives: [f64; LANES]) -> Self
+    {
+        Self {
+            values,
+            derivatives,
+        }
+    }
+
+    fn map(&self, value: impl Fn(f64) -> f64, derivative: impl Fn(f64) -> f64) -> Self
+    {
+        Self {
+            values: array::from_fn(|i| value(self.values[i])),
+            derivatives: array::from_fn(|i| chain(self.derivatives[i], derivative(self.values[i]))),
+        }
+    }
+}
+
+impl<const LANES: usize> Add for Lanes<LANES>
+{
+    type Output = Lanes<LANES>;
+
+    fn add(self, other: Lanes<LANES>) -> Lanes<LANES>
+    {
+        Lanes {
+            values: array::from_fn(|i| self.values[i] + other.values[i]),
+            derivatives: array::from_fn(|i| self.derivatives[i] + other.derivatives[i]),
+        }
+    }
+}
+
+impl<const LANES: usize> Sub for Lanes<LANES>
+{
+    type Output = Lanes<LANES>;
+
+    fn sub(self, other: Lanes<LANES>) -> Lanes<LANES>
+    {
+        Lanes {
+            values: array::from_fn(|i| self.values[i] - other.values[i]),
+            derivatives: array::from_fn(|i| self.derivatives[i] - other.derivatives[i]),
+        }
+    }
+}
+
+impl<const LANES: usize> Mul for Lanes<LANES>
+{
+    type Output = Lanes<LANES>;
+
+    fn mul(self, other: Lanes<LANES>) -> Lanes<LANES>
+    {
+        Lanes {
+            values: array::from_fn(|i| self.values[i] * other.values[i]),
+            derivatives: array::from_fn(|i| {
+                self.derivatives[i] * other.values[i] + self.values[i] * other.derivatives[i]
+            }),
+        }
+    }
+}
+
+impl<const LANES: usize> Div for Lanes<LANES>
+{
+    type Output = Lanes<LANES>;
+
+    fn div(self, other: Lanes<LANES>) -> Lanes<LANES>
+    {
+        Lanes {
+            values: array::from_fn(|i| self.values[i] / other.values[i]),
+            derivatives: array::from_fn(|i| {
+                (self.derivatives[i] * other.values[i] - self.values[i] * other.derivatives[i])
+                    / (other.values[i] * other.values[i])
+            }),
+        }
+    }
+}
+
+impl<const LANES: usize> Neg for Lanes<LANES>
+{
+    type Output = Lanes<LANES>;
+
+    fn neg(self) -> Lanes<LANES>
+    {
+        Lanes {
+            values: self.values.map(|value| -value),
+            derivatives: self.derivatives.map(|derivative| -derivative),
+        }
+    }
+}
+
+impl<const LANES: usize> ChainRule for Lanes<LANES>
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        self.map(|x| function.value(x), |x| function.derivative(x))
+    }
+}
+
+impl<const LANES: usize> Scalar for Lanes<LANES>
+{
+    fn constant(value: f64) -> Self
+    {
+        Self {
+            values: [value; LANES],
+            derivatives: [0.0; LANES],
+        }
+    }
+
+    fn sqrt(self) -> Self
+    {
+        self.map(math::sqrt, |x| 0.5 / math::sqrt(x))
+    }
+
+    fn sin(self) -> Self
+    {
+        self.map(math::sin, math::cos)
+    }
+
+    fn cos(self) -> Self
+    {
+        self.map(math::cos, |x| -math::sin(x))
+    }
+
+    fn tan(self) -> Self
+    {
+        self.map(math::tan, |x| {
+            let sec = 1.0 / math::cos(x);
+            sec * sec
+        })
+    }
+
+    fn ln(self) -> Self
+    {
+        self.map(math::ln, |x| 1.0 / x)
+    }
+
+    fn exp(self) -> Self
+    {
+        self.map(math::exp, math::exp)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        let lanes: [(f64, f64); LANES] =
+            array::from_fn(|i| power(self.values[i], self.derivatives[i], exponent));
+        Self {
+            values: lanes.map(|(value, _)| value),
+            derivatives: lanes.map(|(_, derivative)| derivative),
+        }
+    }
+}
+
+pub type BatchResult<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize> =
+    ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT]);
+
+// Evaluates LANES points at a time. The last chunk is padded with copies of its last point.
+pub fn evaluate_batch_with_lanes<
+    const LANES: usize,
+    const VARIABLE_COUNT: usize,
+    const OUTPUT_COUNT: usize,
+>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    points: &[[f64; VARIABLE_COUNT]],
+) -> Vec<BatchResult<VARIABLE_COUNT, OUTPUT_COUNT>>
+{
+    assert!(LANES > 0);
+    let mut results =
+        vec![([0.0; OUTPUT_COUNT], [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT]); points.len()];
+
+    for (chunk, chunk_results) in points.chunks(LANES).zip(results.chunks_mut(LANES)) {
+        let last = chunk.len() - 1;
+        let values: [[f64; LANES]; VARIABLE_COUNT] =
+            array::from_fn(|variable| array::from_fn(|lane| chunk[lane.min(last)][variable]));
+
+        for input_index in 0..VARIABLE_COUNT {
+            let variables: [Lanes<LANES>; VARIABLE_COUNT] = array::from_fn(|variable| Lanes {
+                values: values[variable],
+                derivatives: [if variable == input_index { 1.0 } else { 0.0 }; LANES],
+            });
+            let outputs = objective.call(variables);
+
+            for (output_index, output) in outputs.iter().enumerate() {
+                for (lane, (values, jacobian)) in chunk_results.iter_mut().enumerate() {
+                    values[output_index] = output.values[lane];
+                    jacobian[output_index][input_index] = output.derivatives[lane];
+                }
+            }
+        }
+    }
+    results
+}
+
+pub fn evaluate_batch<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    points: &[[f64; VARIABLE_COUNT]],
+) -> Vec<BatchResult<VARIABLE_COUNT, OUTPUT_COUNT>>
+{
+    evaluate_batch_with_lanes::<DEFAULT_LANES, VARIABLE_COUNT, OUTPUT_COUNT>(objective, points)
+}
+
+// Splits the points into blocks of whole lane groups and evaluates the blocks in parallel. Each
+// lane group sees exactly the same points as in evaluate_batch_with_lanes, so the results are
+// bit-identical to the serial path.
+#[cfg(feature = "rayon")]
+pub fn par_evaluate_batch_with_lanes<
+    const LANES: usize,
+    const VARIABLE_COUNT: usize,
+    const OUTPUT_COUNT: usize,
+>(
+    objective: &(impl Objective<VARIABLE_COUNT, OUTPUT_COUNT> + Sync),
+    points: &[[f64; VARIABLE_COUNT]],
+) -> Vec<BatchResult<VARIABLE_COUNT, OUTPUT_COUNT>>
+{
+    use rayon::prelude::*;
+
+    const GROUPS_PER_BLOCK: usize = 64;
+
+    assert!(LANES > 0);
+    let mut results =
+        vec![([0.0; OUTPUT_COUNT], [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT]); points.len()];
+    points
+        .par_chunks(LANES * GROUPS_PER_BLOCK)
+        .zip(results.par_chunks_mut(LANES * GROUPS_PER_BLOCK))
+        .for_each(|(block, block_results)| {
+            let block =
+                evaluate_batch_with_lanes::<LANES, VARIABLE_COUNT, OUTPUT_COUNT>(objective, block);
+            block_results.copy_from_slice(&block);
+        });
+    results
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_evaluate_batch<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &(impl Objective<VARIABLE_COUNT, OUTPUT_COUNT> + Sync),
+    points: &[[f64; VARIABLE_COUNT]],
+) -> Vec<BatchResult<VARIABLE_COUNT, OUTPUT_COUNT>>
+{
+    par_evaluate_batch_with_lanes::<DEFAULT_LANES, VARIABLE_COUNT, OUTPUT_COUNT>(objective, points)
+}
@@ -0,0 +1,313 @@
+use core::fmt;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::custom::{ChainRule, CustomFn};
+use super::evaluate::*;
+use super::functions;
+use super::scalar::*;
+use super::variable::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Primitive
+{
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Ln,
+    Exp,
+    Pow,
+    Custom,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DomainErrorKind
+{
+    // The argument is outside the domain of the primitive, e.g. ln(-1) or a division by zero.
+    OutOfDomain,
+    // Finite operands gave an infinite or NaN value or derivative, e.g. the derivative of sqrt
+    // at 0 or exp overflowing.
+    NonFinite,
+}
+
+// The first primitive that went wrong, argument is its (first) operand, or the denominator for
+// Div.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DomainError
+{
+    pub primitive: Primitive,
+    pub argument: f64,
+    pub kind: DomainErrorKind,
+}
+
+impl fmt::Display for DomainError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.kind {
+            DomainErrorKind::OutOfDomain => {
+                write!(f, "{:?} is undefined at {}", self.primitive, self.argument)
+            }
+            DomainErrorKind::NonFinite => write!(
+                f,
+                "{:?} at {} produced a non-finite result",
+                self.primitive, self.argument
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DomainError {}
+
+// Dual number which remembers the first domain error on the way to it. The error travels with the
+// value, so along every chain of operations the recorded error is the earliest one. Where two
+// operands both carry an error the left one is kept.
+#[derive(Debug, Copy, Clone)]
+pub struct Checked
+{
+    pub variable: Variable,
+    pub error: Option<DomainError>,
+}
+
+fn is_finite(variable: Variable) -> bool
+{
+    variable.value.is_finite() && variable.derivative.is_finite()
+}
+
+impl Checked
+{
+    pub fn new(variable: Variable) -> Self
+    {
+        Self {
+            variable,
+            error: None,
+        }
+    }
+
+    fn unary(
+        self,
+        primitive: Primitive,
+        out_of_domain: bool,
+        apply: impl Fn(Variable) -> Variable,
+    ) -> Self
+    {
+        let result = apply(self.variable);
+        let error = self.error.or_else(|| {
+            let kind = if out_of_domain {
+                DomainErrorKind::OutOfDomain
+            } else if is_finite(self.variable) && !is_finite(result) {
+                DomainErrorKind::NonFinite
+            } else {
+                return None;
+            };
+            Some(DomainError {
+                primitive,
+                argument: self.variable.value,
+                kind,
+            })
+        });
+        Self {
+            variable: result,
+            error,
+        }
+    }
+
+    fn binary(
+        self,
+        other: Checked,
+        primitive: Primitive,
+        apply: impl Fn(Variable, Variable) -> Variable,
+    ) -> Self
+    {
+        let result = apply(self.variable, other.variable);
+        let error = self.error.or(other.error).or_else(|| {
+            let (argument, kind) = if primitive == Primitive::Div && other.variable.value == 0.0 {
+                (other.variable.value, DomainErrorKind::OutOfDomain)
+            } else if is_finite(self.variable) && is_finite(other.variable) && !is_finite(result) {
+                (self.variable.value, DomainErrorKind::NonFinite)
+            } else {
+                return None;
+            };
+            Some(DomainError {
+                primitive,
+                argument,
+                kind,
+            })
+        });
+        Self {
+            variable: result,
+            error,
+        }
+    }
+}
+
+impl Add for Checked
+{
+    type Output = Checked;
+
+    fn add(self, other: Checked) -> Checked
+    {
+        self.binary(other, Primitive::Add, |a, b| a + b)
+    }
+}
+
+impl Sub for Checked
+{
+    type Output = Checked;
+
+    fn sub(self, other: Checked) -> Checked
+    {
+        self.binary(other, Primitive::Sub, |a, b| a - b)
+    }
+}
+
+impl Mul for Checked
+{
+    type Output = Checked;
+
+    fn mul(self, other: Checked) -> Checked
+    {
+        self.binary(other, Primitive::Mul, |a, b| a * b)
+    }
+}
+
+impl Div for Checked
+{
+    type Output = Checked;
+
+    fn div(self, other: Checked) -> Checked
+    {
+        self.binary(other, Primitive::Div, |a, b| a / b)
+    }
+}
+
+impl Neg for Checked
+{
+    type Output = Checked;
+
+    fn neg(self) -> Checked
+    {
+        self.unary(Primitive::Neg, false, |a| -a)
+    }
+}
+
+// Custom primitives have no known domain, only non-finite results are reported.
+impl ChainRule for Checked
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        self.unary(Primitive::Custom, false, |a| a.apply_custom(function))
+    }
+}
+
+impl Scalar for Checked
+{
+    fn constant(value: f64) -> Self
+    {
+        Checked::new(Variable::with_derivative(value, 0.0))
+    }
+
+    fn sqrt(self) -> Self
+    {
+        let out_of_domain = self.variable.value < 0.0;
+        self.unary(Primitive::Sqrt, out_of_domain, functions::sqrt)
+    }
+
+    fn sin(self) -> Self
+    {
+        self.unary(Primitive::Sin, false, functions::sin)
+    }
+
+    fn cos(self) -> Self
+    {
+        self.unary(Primitive::Cos, false, functions::cos)
+    }
+
+    fn tan(self) -> Self
+    {
+        self.unary(Primitive::Tan, false, functions::tan)
+    }
+
+    fn ln(self) -> Self
+    {
+        let out_of_domain = self.variable.value <= 0.0;
+        self.unary(Primitive::Ln, out_of_domain, functions::ln)
+    }
+
+    fn exp(self) -> Self
+    {
+        self.unary(Primitive::Exp, false, functions::exp)
+    }
+
+    // Negative bases only have real powers for integer exponents, zero only for positive ones.
+    fn powf(self, exponent: f64) -> Self
+    {
+        let value = self.variable.value;
+        let out_of_domain =
+            (value < 0.0 && exponent % 1.0 != 0.0) || (value == 0.0 && exponent < 0.0);
+        self.unary(Primitive::Pow, out_of_domain, |a| {
+            functions::pow(a, exponent)
+        })
+    }
+}
+
+impl Real for Checked
+{
+    fn value(&self) -> f64
+    {
+        self.variable.value
+    }
+}
+
+// Evaluates like evaluate_with(objective, x, Backend::Dual), values and jacobian are returned as
+// computed, together with a domain error that reached an output. This is the tracking mode: it
+// never stops, it only says where a NaN or infinity came from.
+//
+// Errors travel with the values, so the one reported is the earliest along the chain of operations
+// of the lowest numbered output that has one, in the first pass that has one. That is not always
+// the first error in program order, and errors in intermediate values that no output uses are not
+// reported at all.
+pub fn evaluate_tracked<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+) -> (
+    [f64; OUTPUT_COUNT],
+    [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    Option<DomainError>,
+)
+{
+    let mut values = [0.0; OUTPUT_COUNT];
+    let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
+    let mut first_error = None;
+
+    for input_index in 0..VARIABLE_COUNT {
+        let mut variables = x.map(Checked::constant);
+        variables[input_index].variable.derivative = 1.0;
+        let outputs = objective.call(variables);
+        for (output_index, output) in outputs.into_iter().enumerate() {
+            values[output_index] = output.variable.value;
+            jacobian[output_index][input_index] = output.variable.derivative;
+            first_error = first_error.or(output.error);
+        }
+    }
+    (values, jacobian, first_error)
+}
+
+// Checked evaluation, fails with the error evaluate_tracked reports instead of returning NaN or
+// infinity.
+pub fn try_evaluate<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+) -> Result<([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT]), DomainError>
+{
+    match evaluate_tracked(objective, x) {
+        (_, _, Some(error)) => Err(error),
+        (values, jacobian, None) => Ok((values, jacobian)),
+    }
+}
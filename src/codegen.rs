@@ -0,0 +1,122 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::expression::*;
+
+fn literal(value: f64) -> String
+{
+    if value.is_nan() {
+        "f64::NAN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 {
+            "f64::INFINITY".to_string()
+        } else {
+            "f64::NEG_INFINITY".to_string()
+        }
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn operand(graph: &ExpressionGraph, index: usize) -> String
+{
+    match graph.nodes[index] {
+        Node::Input(input) => format!("x[{}]", input),
+        Node::Constant(value) => literal(value),
+        _ => format!("v{}", index),
+    }
+}
+
+// Negative literals need parentheses as method receivers and after a unary minus.
+fn receiver(graph: &ExpressionGraph, index: usize) -> String
+{
+    let operand = operand(graph, index);
+    if operand.starts_with('-') {
+        format!("({})", operand)
+    } else {
+        operand
+    }
+}
+
+fn statement(graph: &ExpressionGraph, index: usize) -> Option<String>
+{
+    let expression = match graph.nodes[index] {
+        Node::Input(_) | Node::Constant(_) => return None,
+        Node::Unary(UnaryOperation::Neg, a) => format!("-{}", receiver(graph, a)),
+        Node::Unary(operation, a) => format!("{}.{}()", receiver(graph, a), operation.name()),
+        Node::Binary(operation, a, b) => format!(
+            "{} {} {}",
+            operand(graph, a),
+            operation.symbol(),
+            operand(graph, b)
+        ),
+        Node::Powf(a, exponent) => format!("{}.powf({})", receiver(graph, a), literal(exponent)),
+        Node::Custom(function, a) => format!("custom{}({})", function, operand(graph, a)),
+        Node::CustomDerivative(function, a) => {
+            format!("custom{}_derivative({})", function, operand(graph, a))
+        }
+    };
+    Some(format!("    let v{} = {};\n", index, expression))
+}
+
+// Emits a standalone function `fn name(x: [f64; N]) -> ([f64; M], [[f64; N]; M])` which computes
+// the values and the jacobian of the graph with plain f64 arithmetic. The derivatives are added to
+// the same graph before emitting, so every subexpression shared between values and derivatives is
+// only computed once. Custom functions are called as custom0(x) and custom0_derivative(x),
+// numbered like ExpressionGraph::functions, the code including the output has to define them.
+pub fn generate_rust(graph: &ExpressionGraph, name: &str) -> String
+{
+    let input_count = graph.input_count;
+    let output_count = graph.outputs.len();
+
+    let mut combined = graph.clone();
+    let mut derivatives = Vec::with_capacity(output_count * input_count);
+    for &output in &graph.outputs {
+        for input in 0..input_count {
+            derivatives.push(combined.differentiate(output, input));
+        }
+    }
+    combined.outputs.extend(derivatives);
+    let combined = combined.simplify();
+
+    let mut code = format!(
+        "pub fn {}(x: [f64; {}]) -> ([f64; {}], [[f64; {}]; {}])\n{{\n",
+        name, input_count, output_count, input_count, output_count
+    );
+    for index in 0..combined.nodes.len() {
+        if let Some(statement) = statement(&combined, index) {
+            code.push_str(&statement);
+        }
+    }
+
+    let (values, jacobian) = combined.outputs.split_at(output_count);
+    let values: Vec<String> = values.iter().map(|&v| operand(&combined, v)).collect();
+    let rows: Vec<String> = (0..output_count)
+        .map(|row| {
+            let row: Vec<String> = jacobian[row * input_count..(row + 1) * input_count]
+                .iter()
+                .map(|&d| operand(&combined, d))
+                .collect();
+            format!("[{}]", row.join(", "))
+        })
+        .collect();
+    code.push_str(&format!(
+        "    ([{}], [{}])\n}}\n",
+        values.join(", "),
+        rows.join(", ")
+    ));
+    code
+}
+
+// Helper for build scripts: writes the generated functions to a file which the crate can pull in
+// with include!.
+pub fn write_rust(path: impl AsRef<Path>, functions: &[String]) -> io::Result<()>
+{
+    let mut code = String::from("// Generated by autocrab, do not edit.\n");
+    for function in functions {
+        code.push('\n');
+        code.push_str(function);
+    }
+    fs::write(path, code)
+}
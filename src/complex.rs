@@ -0,0 +1,597 @@
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::custom::{ChainRule, CustomFn};
+use crate::math;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Complex
+{
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex
+{
+    pub fn new(re: f64, im: f64) -> Self
+    {
+        Self { re, im }
+    }
+
+    pub fn from_polar(radius: f64, angle: f64) -> Self
+    {
+        Self {
+            re: radius * math::cos(angle),
+            im: radius * math::sin(angle),
+        }
+    }
+
+    pub fn conj(&self) -> Self
+    {
+        Self {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+
+    pub fn norm_squared(&self) -> f64
+    {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn abs(&self) -> f64
+    {
+        math::hypot(self.re, self.im)
+    }
+
+    pub fn arg(&self) -> f64
+    {
+        math::atan2(self.im, self.re)
+    }
+
+    pub fn exp(&self) -> Self
+    {
+        Self::from_polar(math::exp(self.re), self.im)
+    }
+
+    // Principal branch, the cut lies along the negative real axis.
+    pub fn ln(&self) -> Self
+    {
+        Self {
+            re: math::ln(self.abs()),
+            im: self.arg(),
+        }
+    }
+
+    // (|z| + |re|) / 2 instead of the polar form, so roots on the axes are exact, e.g. sqrt(-1) is
+    // exactly i. The sign of im picks the side of the cut like arg does.
+    pub fn sqrt(&self) -> Self
+    {
+        let radius = self.abs();
+        if radius == 0.0 {
+            return Self::new(0.0, self.im);
+        }
+        let root = math::sqrt(0.5 * (radius + self.re.abs()));
+        if self.re >= 0.0 {
+            Self::new(root, self.im / (2.0 * root))
+        } else {
+            Self::new(self.im.abs() / (2.0 * root), root.copysign(self.im))
+        }
+    }
+
+    // Integer exponents use repeated multiplication like Variable::pow. The polar form loses the
+    // tiny imaginary part of a complex step on the negative real axis, where arg is close to π.
+    pub fn powf(&self, exponent: f64) -> Self
+    {
+        if exponent % 1.0 == 0.0 && exponent.abs() <= i32::MAX as f64 {
+            return self.powi(exponent as i32);
+        }
+        Self::from_polar(math::powf(self.abs(), exponent), exponent * self.arg())
+    }
+
+    pub fn powi(&self, exponent: i32) -> Self
+    {
+        // Square and multiply like math::powi.
+        let mut base = *self;
+        let mut remaining = exponent.unsigned_abs();
+        let mut result = Self::new(1.0, 0.0);
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            remaining >>= 1;
+        }
+        if exponent < 0 {
+            Self::new(1.0, 0.0) / result
+        } else {
+            result
+        }
+    }
+
+    pub fn powc(&self, exponent: Complex) -> Self
+    {
+        (exponent * self.ln()).exp()
+    }
+
+    pub fn sin(&self) -> Self
+    {
+        Self {
+            re: math::sin(self.re) * math::cosh(self.im),
+            im: math::cos(self.re) * math::sinh(self.im),
+        }
+    }
+
+    pub fn cos(&self) -> Self
+    {
+        Self {
+            re: math::cos(self.re) * math::cosh(self.im),
+            im: -math::sin(self.re) * math::sinh(self.im),
+        }
+    }
+
+    pub fn tan(&self) -> Self
+    {
+        self.sin() / self.cos()
+    }
+
+    // The real 2x2 jacobian of z -> f(z) seen as a map R^2 -> R^2, when f has this complex
+    // derivative. Holomorphic functions satisfy the Cauchy-Riemann equations, so the jacobian
+    // is always of the form [[a, -b], [b, a]].
+    pub fn to_jacobian(&self) -> [[f64; 2]; 2]
+    {
+        [[self.re, -self.im], [self.im, self.re]]
+    }
+
+    // Inverse of to_jacobian. Averages the entries which are equal for holomorphic functions.
+    pub fn from_jacobian(jacobian: [[f64; 2]; 2]) -> Self
+    {
+        Self {
+            re: 0.5 * (jacobian[0][0] + jacobian[1][1]),
+            im: 0.5 * (jacobian[1][0] - jacobian[0][1]),
+        }
+    }
+}
+
+impl From<f64> for Complex
+{
+    fn from(re: f64) -> Self
+    {
+        Self { re, im: 0.0 }
+    }
+}
+
+impl Add for Complex
+{
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex
+    {
+        Complex {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl Add<f64> for Complex
+{
+    type Output = Complex;
+
+    fn add(self, scalar: f64) -> Complex
+    {
+        Complex {
+            re: self.re + scalar,
+            im: self.im,
+        }
+    }
+}
+
+impl Sub for Complex
+{
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex
+    {
+        Complex {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+impl Sub<f64> for Complex
+{
+    type Output = Complex;
+
+    fn sub(self, scalar: f64) -> Complex
+    {
+        Complex {
+            re: self.re - scalar,
+            im: self.im,
+        }
+    }
+}
+
+impl Mul for Complex
+{
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex
+    {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+impl Mul<f64> for Complex
+{
+    type Output = Complex;
+
+    fn mul(self, scalar: f64) -> Complex
+    {
+        Complex {
+            re: self.re * scalar,
+            im: self.im * scalar,
+        }
+    }
+}
+
+impl Mul<Complex> for f64
+{
+    type Output = Complex;
+
+    fn mul(self, complex: Complex) -> Complex
+    {
+        complex * self
+    }
+}
+
+impl Div for Complex
+{
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex
+    {
+        let denominator = other.norm_squared();
+        Complex {
+            re: (self.re * other.re + self.im * other.im) / denominator,
+            im: (self.im * other.re - self.re * other.im) / denominator,
+        }
+    }
+}
+
+impl Div<f64> for Complex
+{
+    type Output = Complex;
+
+    fn div(self, scalar: f64) -> Complex
+    {
+        Complex {
+            re: self.re / scalar,
+            im: self.im / scalar,
+        }
+    }
+}
+
+impl Neg for Complex
+{
+    type Output = Complex;
+
+    fn neg(self) -> Complex
+    {
+        Complex {
+            re: -self.re,
+            im: -self.im,
+        }
+    }
+}
+
+// Dual number over the complex numbers. For holomorphic functions the derivative is a single
+// complex number, rather than the 2x2 real jacobian of the real and imaginary parts.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComplexVariable
+{
+    pub value: Complex,
+    pub derivative: Complex,
+}
+
+impl ComplexVariable
+{
+    pub fn constant(value: Complex) -> Self
+    {
+        Self {
+            value,
+            derivative: Complex::new(0.0, 0.0),
+        }
+    }
+
+    pub fn seeded(value: Complex) -> Self
+    {
+        Self {
+            value,
+            derivative: Complex::new(1.0, 0.0),
+        }
+    }
+
+    pub fn with_derivative(value: Complex, derivative: Complex) -> Self
+    {
+        Self { value, derivative }
+    }
+
+    fn chain(&self, value: Complex, derivative: Complex) -> Self
+    {
+        Self {
+            value,
+            derivative: self.derivative * derivative,
+        }
+    }
+
+    pub fn exp(&self) -> Self
+    {
+        let exp_value = self.value.exp();
+        self.chain(exp_value, exp_value)
+    }
+
+    pub fn ln(&self) -> Self
+    {
+        self.chain(self.value.ln(), Complex::new(1.0, 0.0) / self.value)
+    }
+
+    pub fn sqrt(&self) -> Self
+    {
+        let root = self.value.sqrt();
+        self.chain(root, Complex::new(0.5, 0.0) / root)
+    }
+
+    pub fn powf(&self, exponent: f64) -> Self
+    {
+        self.chain(
+            self.value.powf(exponent),
+            exponent * self.value.powf(exponent - 1.0),
+        )
+    }
+
+    // z^0 is the constant 1, also at z = 0 where z^-1 is infinite.
+    pub fn powi(&self, exponent: i32) -> Self
+    {
+        let value = self.value.powi(exponent);
+        if exponent == 0 {
+            return Self::with_derivative(value, Complex::new(0.0, 0.0));
+        }
+        // z^(n - 1) as z^n / z when n - 1 does not fit an i32.
+        let slope = match exponent.checked_sub(1) {
+            Some(exponent) => self.value.powi(exponent),
+            None => value / self.value,
+        };
+        self.chain(value, exponent as f64 * slope)
+    }
+
+    pub fn pow(&self, exponent: ComplexVariable) -> Self
+    {
+        (exponent * self.ln()).exp()
+    }
+
+    pub fn sin(&self) -> Self
+    {
+        self.chain(self.value.sin(), self.value.cos())
+    }
+
+    pub fn cos(&self) -> Self
+    {
+        self.chain(self.value.cos(), -self.value.sin())
+    }
+
+    pub fn tan(&self) -> Self
+    {
+        let cos = self.value.cos();
+        self.chain(self.value.tan(), Complex::new(1.0, 0.0) / (cos * cos))
+    }
+
+    pub fn jacobian(&self) -> [[f64; 2]; 2]
+    {
+        self.derivative.to_jacobian()
+    }
+}
+
+// Custom primitives are real functions, they are extended to the value like for Complex and the
+// derivative at its real part multiplies the incoming derivative.
+impl ChainRule for ComplexVariable
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        let derivative = Complex::new(function.derivative(self.value.re), 0.0);
+        self.chain(self.value.apply_custom(function), derivative)
+    }
+}
+
+pub type ComplexFunction = fn(variable: ComplexVariable) -> ComplexVariable;
+
+pub fn evaluate_complex(function: ComplexFunction, z: Complex) -> (Complex, Complex)
+{
+    let res = function(ComplexVariable::seeded(z));
+    (res.value, res.derivative)
+}
+
+impl Add for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn add(self, other: ComplexVariable) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value + other.value,
+            derivative: self.derivative + other.derivative,
+        }
+    }
+}
+
+impl Add<Complex> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn add(self, scalar: Complex) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value + scalar,
+            derivative: self.derivative,
+        }
+    }
+}
+
+impl Add<f64> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn add(self, scalar: f64) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value + scalar,
+            derivative: self.derivative,
+        }
+    }
+}
+
+impl Sub for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn sub(self, other: ComplexVariable) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value - other.value,
+            derivative: self.derivative - other.derivative,
+        }
+    }
+}
+
+impl Sub<Complex> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn sub(self, scalar: Complex) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value - scalar,
+            derivative: self.derivative,
+        }
+    }
+}
+
+impl Sub<f64> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn sub(self, scalar: f64) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value - scalar,
+            derivative: self.derivative,
+        }
+    }
+}
+
+impl Mul for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn mul(self, other: ComplexVariable) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value * other.value,
+            derivative: self.derivative * other.value + self.value * other.derivative,
+        }
+    }
+}
+
+impl Mul<Complex> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn mul(self, scalar: Complex) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value * scalar,
+            derivative: self.derivative * scalar,
+        }
+    }
+}
+
+impl Mul<f64> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn mul(self, scalar: f64) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value * scalar,
+            derivative: self.derivative * scalar,
+        }
+    }
+}
+
+impl Mul<ComplexVariable> for f64
+{
+    type Output = ComplexVariable;
+
+    fn mul(self, variable: ComplexVariable) -> ComplexVariable
+    {
+        variable * self
+    }
+}
+
+impl Div for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn div(self, other: ComplexVariable) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value / other.value,
+            derivative: (self.derivative * other.value - self.value * other.derivative)
+                / (other.value * other.value),
+        }
+    }
+}
+
+impl Div<Complex> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn div(self, scalar: Complex) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value / scalar,
+            derivative: self.derivative / scalar,
+        }
+    }
+}
+
+impl Div<f64> for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn div(self, scalar: f64) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: self.value / scalar,
+            derivative: self.derivative / scalar,
+        }
+    }
+}
+
+impl Neg for ComplexVariable
+{
+    type Output = ComplexVariable;
+
+    fn neg(self) -> ComplexVariable
+    {
+        ComplexVariable {
+            value: -self.value,
+            derivative: -self.derivative,
+        }
+    }
+}
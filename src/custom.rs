@@ -0,0 +1,88 @@
+use crate::interval::Interval;
+use crate::variable::{chain, Variable};
+
+// Scalars that a custom primitive can be applied to. Every Scalar is one, so custom primitives
+// work inside an Objective with any backend. Tracing keeps a copy of the function in the graph,
+// hence the Clone + Send + Sync + 'static bound.
+pub trait ChainRule: Sized
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self;
+}
+
+impl ChainRule for f64
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        function.value(self)
+    }
+}
+
+impl ChainRule for Variable
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        Variable {
+            value: function.value(self.value),
+            derivative: chain(self.derivative, function.derivative(self.value)),
+        }
+    }
+}
+
+pub trait CustomFn
+{
+    fn value(&self, x: f64) -> f64;
+    fn derivative(&self, x: f64) -> f64;
+
+    // Enclosures of the value and the derivative over a whole interval, for interval arithmetic.
+    // Point evaluations cannot bound a function between them, so by default nothing is known.
+    fn value_enclosure(&self, _x: Interval) -> Interval
+    {
+        Interval::ENTIRE
+    }
+
+    fn derivative_enclosure(&self, _x: Interval) -> Interval
+    {
+        Interval::ENTIRE
+    }
+
+    fn apply<T: ChainRule>(&self, x: T) -> T
+    where
+        Self: Clone + Send + Sync + Sized + 'static,
+    {
+        x.apply_custom(self)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct CustomUnary<V, D>
+{
+    value_fn: V,
+    derivative_fn: D,
+}
+
+impl<V, D> CustomFn for CustomUnary<V, D>
+where
+    V: Fn(f64) -> f64,
+    D: Fn(f64) -> f64,
+{
+    fn value(&self, x: f64) -> f64
+    {
+        (self.value_fn)(x)
+    }
+
+    fn derivative(&self, x: f64) -> f64
+    {
+        (self.derivative_fn)(x)
+    }
+}
+
+pub fn custom_unary<V, D>(value_fn: V, derivative_fn: D) -> CustomUnary<V, D>
+where
+    V: Fn(f64) -> f64,
+    D: Fn(f64) -> f64,
+{
+    CustomUnary {
+        value_fn,
+        derivative_fn,
+    }
+}
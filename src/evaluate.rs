@@ -1,3 +1,5 @@
+use super::linalg::*;
+use super::scalar::*;
 use super::variable::*;
 
 fn make_variables_from_values<const VARIABLE_COUNT: usize>(
@@ -7,7 +9,7 @@ fn make_variables_from_values<const VARIABLE_COUNT: usize>(
 {
     assert!(seed_index < VARIABLE_COUNT);
     let mut variables = [Variable::empty(); VARIABLE_COUNT];
-    for (variable, value) in variables.iter_mut().zip(x.into_iter()) {
+    for (variable, value) in variables.iter_mut().zip(x) {
         variable.value = value;
     }
     variables[seed_index].derivative = 1.0;
@@ -17,6 +19,41 @@ fn make_variables_from_values<const VARIABLE_COUNT: usize>(
 pub type ObjectiveFunction<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize> =
     fn(variables: [Variable; VARIABLE_COUNT]) -> [Variable; OUTPUT_COUNT];
 
+// Objectives written against Scalar can be evaluated with any of the scalar types, which is what
+// the alternative backends and tracers need.
+pub trait Objective<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    fn call<T: Scalar>(&self, variables: [T; VARIABLE_COUNT]) -> [T; OUTPUT_COUNT];
+}
+
+// Columns are evaluated in parallel from this many variables on when the rayon feature is
+// enabled, below it the pass is too cheap to be worth a task.
+pub const PARALLEL_COLUMN_THRESHOLD: usize = 16;
+
+fn evaluate_column<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+    input_index: usize,
+) -> [Variable; OUTPUT_COUNT]
+{
+    function(make_variables_from_values(x, input_index))
+}
+
+// Every column is a separate pass which writes its own entries, so the result does not depend on
+// the order in which the passes ran.
+fn store_column<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    values: &mut [f64; OUTPUT_COUNT],
+    jacobian: &mut [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    input_index: usize,
+    column: [Variable; OUTPUT_COUNT],
+)
+{
+    for (output_index, output) in column.into_iter().enumerate() {
+        values[output_index] = output.value;
+        jacobian[output_index][input_index] = output.derivative;
+    }
+}
+
 pub fn evaluate<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
     function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
     x: [f64; VARIABLE_COUNT],
@@ -25,14 +62,33 @@ pub fn evaluate<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
     let mut values = [0.0; OUTPUT_COUNT];
     let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
 
-    for (output_index, value) in values.iter_mut().enumerate() {
-        for (input_index, derivative) in jacobian[output_index].iter_mut().enumerate() {
-            let variables = make_variables_from_values(x, input_index);
-            let res = function(variables);
-            *value = res[output_index].value;
-            *derivative = res[output_index].derivative;
+    #[cfg(feature = "rayon")]
+    if VARIABLE_COUNT >= PARALLEL_COLUMN_THRESHOLD {
+        use rayon::prelude::*;
+
+        let columns: Vec<_> = (0..VARIABLE_COUNT)
+            .into_par_iter()
+            .map(|input_index| evaluate_column(function, x, input_index))
+            .collect();
+        for (input_index, column) in columns.into_iter().enumerate() {
+            store_column(&mut values, &mut jacobian, input_index, column);
         }
+        return (values, jacobian);
+    }
+
+    for input_index in 0..VARIABLE_COUNT {
+        let column = evaluate_column(function, x, input_index);
+        store_column(&mut values, &mut jacobian, input_index, column);
     }
 
     (values, jacobian)
 }
+
+pub fn evaluate_matrix<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: Vector<VARIABLE_COUNT>,
+) -> (Vector<OUTPUT_COUNT>, Matrix<OUTPUT_COUNT, VARIABLE_COUNT>)
+{
+    let (values, jacobian) = evaluate(function, x.0);
+    (Vector(values), Matrix(jacobian))
+}
@@ -0,0 +1,839 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::sync::Arc;
+
+use super::custom::{ChainRule, CustomFn};
+use super::evaluate::*;
+use super::math;
+use super::scalar::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnaryOperation
+{
+    Neg,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Ln,
+    Exp,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BinaryOperation
+{
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+// Nodes refer to their operands by index into ExpressionGraph::nodes, operands always come first.
+// Custom nodes apply the custom function with the given index into ExpressionGraph::functions, or
+// its derivative. Those functions are code, so graphs using them cannot be serialized.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Node
+{
+    Input(usize),
+    Constant(f64),
+    Unary(UnaryOperation, usize),
+    Binary(BinaryOperation, usize, usize),
+    Powf(usize, f64),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(usize, usize),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CustomDerivative(usize, usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum NodeKey
+{
+    Input(usize),
+    Constant(u64),
+    Unary(UnaryOperation, usize),
+    Binary(BinaryOperation, usize, usize),
+    Powf(usize, u64),
+    Custom(usize, usize),
+    CustomDerivative(usize, usize),
+}
+
+// A custom function recorded while tracing, shared between the graphs and tapes built from it.
+#[derive(Clone)]
+pub struct SharedFn(Arc<dyn CustomFn + Send + Sync>);
+
+impl fmt::Debug for SharedFn
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        f.write_str("SharedFn")
+    }
+}
+
+impl PartialEq for SharedFn
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl CustomFn for SharedFn
+{
+    fn value(&self, x: f64) -> f64
+    {
+        self.0.value(x)
+    }
+
+    fn derivative(&self, x: f64) -> f64
+    {
+        self.0.derivative(x)
+    }
+}
+
+impl Node
+{
+    fn key(&self) -> NodeKey
+    {
+        match *self {
+            Node::Input(index) => NodeKey::Input(index),
+            Node::Constant(value) => NodeKey::Constant(value.to_bits()),
+            Node::Unary(operation, a) => NodeKey::Unary(operation, a),
+            Node::Binary(operation, a, b) => NodeKey::Binary(operation, a, b),
+            Node::Powf(a, exponent) => NodeKey::Powf(a, exponent.to_bits()),
+            Node::Custom(function, a) => NodeKey::Custom(function, a),
+            Node::CustomDerivative(function, a) => NodeKey::CustomDerivative(function, a),
+        }
+    }
+
+    fn precedence(&self) -> u8
+    {
+        match *self {
+            Node::Binary(BinaryOperation::Add | BinaryOperation::Sub, _, _) => 1,
+            Node::Binary(BinaryOperation::Mul | BinaryOperation::Div, _, _) => 2,
+            Node::Unary(UnaryOperation::Neg, _) => 3,
+            Node::Constant(value) if value < 0.0 => 3,
+            _ => 4,
+        }
+    }
+}
+
+impl UnaryOperation
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            UnaryOperation::Neg => "-",
+            UnaryOperation::Sqrt => "sqrt",
+            UnaryOperation::Sin => "sin",
+            UnaryOperation::Cos => "cos",
+            UnaryOperation::Tan => "tan",
+            UnaryOperation::Ln => "ln",
+            UnaryOperation::Exp => "exp",
+        }
+    }
+
+    pub fn apply(&self, a: f64) -> f64
+    {
+        match self {
+            UnaryOperation::Neg => -a,
+            UnaryOperation::Sqrt => math::sqrt(a),
+            UnaryOperation::Sin => math::sin(a),
+            UnaryOperation::Cos => math::cos(a),
+            UnaryOperation::Tan => math::tan(a),
+            UnaryOperation::Ln => math::ln(a),
+            UnaryOperation::Exp => math::exp(a),
+        }
+    }
+}
+
+impl BinaryOperation
+{
+    pub fn symbol(&self) -> &'static str
+    {
+        match self {
+            BinaryOperation::Add => "+",
+            BinaryOperation::Sub => "-",
+            BinaryOperation::Mul => "*",
+            BinaryOperation::Div => "/",
+        }
+    }
+
+    pub fn apply(&self, a: f64, b: f64) -> f64
+    {
+        match self {
+            BinaryOperation::Add => a + b,
+            BinaryOperation::Sub => a - b,
+            BinaryOperation::Mul => a * b,
+            BinaryOperation::Div => a / b,
+        }
+    }
+}
+
+// Directed acyclic graph of the operations performed by an objective. Identical nodes are only
+// stored once, so common subexpressions are shared.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SerializedGraph", into = "SerializedGraph")
+)]
+pub struct ExpressionGraph
+{
+    pub nodes: Vec<Node>,
+    pub outputs: Vec<usize>,
+    pub input_count: usize,
+    pub functions: Vec<SharedFn>,
+    lookup: HashMap<NodeKey, usize>,
+}
+
+// The hash-consing lookup is not serialized, it is rebuilt from the nodes when loading.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedGraph
+{
+    nodes: Vec<Node>,
+    outputs: Vec<usize>,
+    input_count: usize,
+}
+
+#[cfg(feature = "serde")]
+impl From<ExpressionGraph> for SerializedGraph
+{
+    fn from(graph: ExpressionGraph) -> Self
+    {
+        Self {
+            nodes: graph.nodes,
+            outputs: graph.outputs,
+            input_count: graph.input_count,
+        }
+    }
+}
+
+// Why a loaded graph was rejected. Operands have to refer to earlier nodes, which is what
+// evaluate and differentiate rely on.
+#[cfg(feature = "serde")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InvalidGraph
+{
+    Operand
+    {
+        node: usize, operand: usize
+    },
+    Input
+    {
+        node: usize, input: usize
+    },
+    Output
+    {
+        output: usize
+    },
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for InvalidGraph
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self {
+            InvalidGraph::Operand { node, operand } => {
+                write!(
+                    f,
+                    "node {} uses node {} which does not precede it",
+                    node, operand
+                )
+            }
+            InvalidGraph::Input { node, input } => {
+                write!(
+                    f,
+                    "node {} reads input {} which does not exist",
+                    node, input
+                )
+            }
+            InvalidGraph::Output { output } => write!(f, "output node {} does not exist", output),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for InvalidGraph {}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedGraph> for ExpressionGraph
+{
+    type Error = InvalidGraph;
+
+    fn try_from(graph: SerializedGraph) -> Result<Self, InvalidGraph>
+    {
+        for (index, node) in graph.nodes.iter().enumerate() {
+            let operands = match *node {
+                Node::Input(input) if input >= graph.input_count => {
+                    return Err(InvalidGraph::Input { node: index, input });
+                }
+                Node::Input(_) | Node::Constant(_) => [None, None],
+                Node::Unary(_, a)
+                | Node::Powf(a, _)
+                | Node::Custom(_, a)
+                | Node::CustomDerivative(_, a) => [Some(a), None],
+                Node::Binary(_, a, b) => [Some(a), Some(b)],
+            };
+            if let Some(operand) = operands
+                .into_iter()
+                .flatten()
+                .find(|&operand| operand >= index)
+            {
+                return Err(InvalidGraph::Operand {
+                    node: index,
+                    operand,
+                });
+            }
+        }
+        if let Some(&output) = graph
+            .outputs
+            .iter()
+            .find(|&&output| output >= graph.nodes.len())
+        {
+            return Err(InvalidGraph::Output { output });
+        }
+
+        let mut lookup = HashMap::with_capacity(graph.nodes.len());
+        for (index, node) in graph.nodes.iter().enumerate() {
+            lookup.entry(node.key()).or_insert(index);
+        }
+        Ok(Self {
+            nodes: graph.nodes,
+            outputs: graph.outputs,
+            input_count: graph.input_count,
+            functions: Vec::new(),
+            lookup,
+        })
+    }
+}
+
+impl ExpressionGraph
+{
+    pub fn new(input_count: usize) -> Self
+    {
+        Self {
+            input_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, node: Node) -> usize
+    {
+        let key = node.key();
+        if let Some(&index) = self.lookup.get(&key) {
+            return index;
+        }
+        self.nodes.push(node);
+        self.lookup.insert(key, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn constant_value(&self, index: usize) -> Option<f64>
+    {
+        match self.nodes[index] {
+            Node::Constant(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // Adds a node after constant folding and removing identities like x * 1 and x + 0.
+    pub fn add_simplified(&mut self, node: Node) -> usize
+    {
+        match node {
+            Node::Unary(operation, a) => {
+                if let Some(a) = self.constant_value(a) {
+                    return self.add(Node::Constant(operation.apply(a)));
+                }
+                if let (UnaryOperation::Neg, Node::Unary(UnaryOperation::Neg, inner)) =
+                    (operation, self.nodes[a])
+                {
+                    return inner;
+                }
+            }
+            Node::Binary(operation, a, b) => {
+                let left = self.constant_value(a);
+                let right = self.constant_value(b);
+                if let (Some(left), Some(right)) = (left, right) {
+                    return self.add(Node::Constant(operation.apply(left, right)));
+                }
+                match (operation, left, right) {
+                    (BinaryOperation::Add, Some(0.0), _) => return b,
+                    (BinaryOperation::Add | BinaryOperation::Sub, _, Some(0.0)) => return a,
+                    (BinaryOperation::Sub, Some(0.0), _) => {
+                        return self.add_simplified(Node::Unary(UnaryOperation::Neg, b))
+                    }
+                    (BinaryOperation::Mul, Some(1.0), _) => return b,
+                    (BinaryOperation::Mul, Some(-1.0), _) => {
+                        return self.add_simplified(Node::Unary(UnaryOperation::Neg, b))
+                    }
+                    (BinaryOperation::Mul, _, Some(-1.0)) => {
+                        return self.add_simplified(Node::Unary(UnaryOperation::Neg, a))
+                    }
+                    (BinaryOperation::Mul | BinaryOperation::Div, _, Some(1.0)) => return a,
+                    _ => {}
+                }
+            }
+            Node::Powf(a, exponent) => {
+                if let Some(a) = self.constant_value(a) {
+                    return self.add(Node::Constant(math::powf(a, exponent)));
+                }
+                if exponent == 1.0 {
+                    return a;
+                }
+                if exponent == 0.0 {
+                    return self.add(Node::Constant(1.0));
+                }
+            }
+            Node::Custom(function, a) => {
+                if let Some(a) = self.constant_value(a) {
+                    return self.add(Node::Constant(self.functions[function].value(a)));
+                }
+            }
+            Node::CustomDerivative(function, a) => {
+                if let Some(a) = self.constant_value(a) {
+                    return self.add(Node::Constant(self.functions[function].derivative(a)));
+                }
+            }
+            _ => {}
+        }
+        self.add(node)
+    }
+
+    // Rebuilds the graph with add_simplified, dropping nodes the outputs no longer use.
+    pub fn simplify(&self) -> ExpressionGraph
+    {
+        let mut simplified = ExpressionGraph::new(self.input_count);
+        simplified.functions = self.functions.clone();
+        let mut mapping = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let node = match *node {
+                Node::Unary(operation, a) => Node::Unary(operation, mapping[a]),
+                Node::Binary(operation, a, b) => Node::Binary(operation, mapping[a], mapping[b]),
+                Node::Powf(a, exponent) => Node::Powf(mapping[a], exponent),
+                Node::Custom(function, a) => Node::Custom(function, mapping[a]),
+                Node::CustomDerivative(function, a) => Node::CustomDerivative(function, mapping[a]),
+                node => node,
+            };
+            mapping.push(simplified.add_simplified(node));
+        }
+        simplified.outputs = self.outputs.iter().map(|&output| mapping[output]).collect();
+        simplified.prune()
+    }
+
+    fn prune(&self) -> ExpressionGraph
+    {
+        let mut used = vec![false; self.nodes.len()];
+        for &output in &self.outputs {
+            used[output] = true;
+        }
+        for index in (0..self.nodes.len()).rev() {
+            if !used[index] {
+                continue;
+            }
+            match self.nodes[index] {
+                Node::Unary(_, a)
+                | Node::Powf(a, _)
+                | Node::Custom(_, a)
+                | Node::CustomDerivative(_, a) => used[a] = true,
+                Node::Binary(_, a, b) => {
+                    used[a] = true;
+                    used[b] = true;
+                }
+                _ => {}
+            }
+        }
+
+        let mut pruned = ExpressionGraph::new(self.input_count);
+        pruned.functions = self.functions.clone();
+        let mut mapping = vec![0; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !used[index] {
+                continue;
+            }
+            let node = match *node {
+                Node::Unary(operation, a) => Node::Unary(operation, mapping[a]),
+                Node::Binary(operation, a, b) => Node::Binary(operation, mapping[a], mapping[b]),
+                Node::Powf(a, exponent) => Node::Powf(mapping[a], exponent),
+                Node::Custom(function, a) => Node::Custom(function, mapping[a]),
+                Node::CustomDerivative(function, a) => Node::CustomDerivative(function, mapping[a]),
+                node => node,
+            };
+            mapping[index] = pruned.add(node);
+        }
+        pruned.outputs = self.outputs.iter().map(|&output| mapping[output]).collect();
+        pruned
+    }
+
+    // Adds a chain rule term of differentiate. Like variable::chain, a term whose incoming
+    // derivative is the constant zero is zero, add_simplified alone keeps 0 * x since x may be
+    // infinite or NaN.
+    fn add_term(&mut self, derivative: usize, node: Node) -> usize
+    {
+        if self.constant_value(derivative) == Some(0.0) {
+            derivative
+        } else {
+            self.add_simplified(node)
+        }
+    }
+
+    // Adds the nodes of d node / d input and returns the index of the derivative.
+    pub fn differentiate(&mut self, node: usize, input: usize) -> usize
+    {
+        let mut derivatives: Vec<usize> = Vec::with_capacity(node + 1);
+        for index in 0..=node {
+            let derivative = match self.nodes[index] {
+                Node::Input(i) => self.add(Node::Constant(if i == input { 1.0 } else { 0.0 })),
+                Node::Constant(_) => self.add(Node::Constant(0.0)),
+                Node::Unary(operation, a) => {
+                    let da = derivatives[a];
+                    // Rules of the form f'(a) = 1 / g(a) divide by g(a) instead of multiplying by
+                    // its reciprocal, which keeps the printed formulas readable.
+                    let (local, divide) = match operation {
+                        UnaryOperation::Neg => (self.add(Node::Constant(-1.0)), false),
+                        UnaryOperation::Sqrt => {
+                            let two = self.add(Node::Constant(2.0));
+                            let denominator =
+                                self.add_simplified(Node::Binary(BinaryOperation::Mul, two, index));
+                            (denominator, true)
+                        }
+                        UnaryOperation::Sin => (
+                            self.add_simplified(Node::Unary(UnaryOperation::Cos, a)),
+                            false,
+                        ),
+                        UnaryOperation::Cos => {
+                            let sin = self.add_simplified(Node::Unary(UnaryOperation::Sin, a));
+                            (
+                                self.add_simplified(Node::Unary(UnaryOperation::Neg, sin)),
+                                false,
+                            )
+                        }
+                        UnaryOperation::Tan => {
+                            let cos = self.add_simplified(Node::Unary(UnaryOperation::Cos, a));
+                            (self.add_simplified(Node::Powf(cos, 2.0)), true)
+                        }
+                        UnaryOperation::Ln => (a, true),
+                        UnaryOperation::Exp => (index, false),
+                    };
+                    let operation = if divide {
+                        BinaryOperation::Div
+                    } else {
+                        BinaryOperation::Mul
+                    };
+                    self.add_term(da, Node::Binary(operation, da, local))
+                }
+                Node::Binary(operation, a, b) => {
+                    let da = derivatives[a];
+                    let db = derivatives[b];
+                    match operation {
+                        BinaryOperation::Add | BinaryOperation::Sub => {
+                            self.add_simplified(Node::Binary(operation, da, db))
+                        }
+                        BinaryOperation::Mul => {
+                            let left = self.add_term(da, Node::Binary(BinaryOperation::Mul, da, b));
+                            let right =
+                                self.add_term(db, Node::Binary(BinaryOperation::Mul, a, db));
+                            self.add_simplified(Node::Binary(BinaryOperation::Add, left, right))
+                        }
+                        BinaryOperation::Div => {
+                            let left = self.add_term(da, Node::Binary(BinaryOperation::Div, da, b));
+                            let quotient =
+                                self.add_simplified(Node::Binary(BinaryOperation::Div, index, b));
+                            let right =
+                                self.add_term(db, Node::Binary(BinaryOperation::Mul, quotient, db));
+                            self.add_simplified(Node::Binary(BinaryOperation::Sub, left, right))
+                        }
+                    }
+                }
+                Node::Powf(a, exponent) => {
+                    let da = derivatives[a];
+                    let power = self.add_simplified(Node::Powf(a, exponent - 1.0));
+                    let factor = self.add(Node::Constant(exponent));
+                    let local =
+                        self.add_simplified(Node::Binary(BinaryOperation::Mul, factor, power));
+                    self.add_term(da, Node::Binary(BinaryOperation::Mul, da, local))
+                }
+                Node::Custom(function, a) => {
+                    let local = self.add_simplified(Node::CustomDerivative(function, a));
+                    let da = derivatives[a];
+                    self.add_term(da, Node::Binary(BinaryOperation::Mul, da, local))
+                }
+                // Custom functions only come with their first derivative.
+                Node::CustomDerivative(_, a) => {
+                    let unknown = self.add(Node::Constant(f64::NAN));
+                    let da = derivatives[a];
+                    self.add_term(da, Node::Binary(BinaryOperation::Mul, da, unknown))
+                }
+            };
+            derivatives.push(derivative);
+        }
+        derivatives[node]
+    }
+
+    // Graph whose outputs are the derivatives of this graph's outputs with respect to one input.
+    pub fn derivative(&self, input: usize) -> ExpressionGraph
+    {
+        let mut graph = self.clone();
+        graph.outputs = self
+            .outputs
+            .iter()
+            .map(|&output| graph.differentiate(output, input))
+            .collect();
+        graph.simplify()
+    }
+
+    pub fn evaluate(&self, x: &[f64]) -> Vec<f64>
+    {
+        assert_eq!(x.len(), self.input_count);
+        let mut values: Vec<f64> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match *node {
+                Node::Input(index) => x[index],
+                Node::Constant(value) => value,
+                Node::Unary(operation, a) => operation.apply(values[a]),
+                Node::Binary(operation, a, b) => operation.apply(values[a], values[b]),
+                Node::Powf(a, exponent) => math::powf(values[a], exponent),
+                Node::Custom(function, a) => self.functions[function].value(values[a]),
+                Node::CustomDerivative(function, a) => {
+                    self.functions[function].derivative(values[a])
+                }
+            };
+            values.push(value);
+        }
+        self.outputs.iter().map(|&output| values[output]).collect()
+    }
+
+    // Infix formula of a node, with only the parentheses the precedence rules need.
+    pub fn formula(&self, index: usize) -> String
+    {
+        match self.nodes[index] {
+            Node::Input(i) => format!("x{}", i),
+            Node::Constant(value) => format!("{}", value),
+            Node::Unary(UnaryOperation::Neg, a) => format!("-{}", self.operand(a, 3, true)),
+            Node::Unary(operation, a) => format!("{}({})", operation.name(), self.formula(a)),
+            Node::Powf(a, exponent) => format!("pow({}, {})", self.formula(a), exponent),
+            Node::Custom(function, a) => format!("custom{}({})", function, self.formula(a)),
+            Node::CustomDerivative(function, a) => {
+                format!("custom{}_derivative({})", function, self.formula(a))
+            }
+            Node::Binary(operation, a, b) => {
+                let precedence = self.nodes[index].precedence();
+                let strict = matches!(operation, BinaryOperation::Sub | BinaryOperation::Div);
+                format!(
+                    "{} {} {}",
+                    self.operand(a, precedence, false),
+                    operation.symbol(),
+                    self.operand(b, precedence, strict)
+                )
+            }
+        }
+    }
+
+    fn operand(&self, index: usize, precedence: u8, strict: bool) -> String
+    {
+        let operand_precedence = self.nodes[index].precedence();
+        if operand_precedence < precedence || (strict && operand_precedence == precedence) {
+            format!("({})", self.formula(index))
+        } else {
+            self.formula(index)
+        }
+    }
+}
+
+impl fmt::Display for ExpressionGraph
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        for (i, &output) in self.outputs.iter().enumerate() {
+            writeln!(f, "y{} = {}", i, self.formula(output))?;
+        }
+        Ok(())
+    }
+}
+
+// The graph recorded by the objective currently being traced on this thread.
+thread_local! {
+    static GRAPH: RefCell<ExpressionGraph> = RefCell::new(ExpressionGraph::default());
+}
+
+// Scalar which records every operation performed on it into the expression graph. The value is
+// carried along so that objectives which branch on values record the branch taken at the given
+// point.
+#[derive(Debug, Copy, Clone)]
+pub struct Tracer
+{
+    pub value: f64,
+    pub node: usize,
+}
+
+impl Tracer
+{
+    fn record(node: Node, value: f64) -> Self
+    {
+        let node = GRAPH.with(|graph| graph.borrow_mut().add(node));
+        Self { value, node }
+    }
+
+    fn unary(&self, operation: UnaryOperation) -> Self
+    {
+        Self::record(
+            Node::Unary(operation, self.node),
+            operation.apply(self.value),
+        )
+    }
+
+    fn binary(&self, operation: BinaryOperation, other: &Self) -> Self
+    {
+        Self::record(
+            Node::Binary(operation, self.node, other.node),
+            operation.apply(self.value, other.value),
+        )
+    }
+}
+
+impl Add for Tracer
+{
+    type Output = Tracer;
+
+    fn add(self, other: Tracer) -> Tracer
+    {
+        self.binary(BinaryOperation::Add, &other)
+    }
+}
+
+impl Sub for Tracer
+{
+    type Output = Tracer;
+
+    fn sub(self, other: Tracer) -> Tracer
+    {
+        self.binary(BinaryOperation::Sub, &other)
+    }
+}
+
+impl Mul for Tracer
+{
+    type Output = Tracer;
+
+    fn mul(self, other: Tracer) -> Tracer
+    {
+        self.binary(BinaryOperation::Mul, &other)
+    }
+}
+
+impl Div for Tracer
+{
+    type Output = Tracer;
+
+    fn div(self, other: Tracer) -> Tracer
+    {
+        self.binary(BinaryOperation::Div, &other)
+    }
+}
+
+impl Neg for Tracer
+{
+    type Output = Tracer;
+
+    fn neg(self) -> Tracer
+    {
+        self.unary(UnaryOperation::Neg)
+    }
+}
+
+// The function is stored in the graph, so the graph can evaluate and differentiate it at other
+// points. Every application records its own copy.
+impl ChainRule for Tracer
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        let value = function.value(self.value);
+        let index = GRAPH.with(|graph| {
+            let mut graph = graph.borrow_mut();
+            graph.functions.push(SharedFn(Arc::new(function.clone())));
+            graph.functions.len() - 1
+        });
+        Self::record(Node::Custom(index, self.node), value)
+    }
+}
+
+impl Scalar for Tracer
+{
+    fn constant(value: f64) -> Self
+    {
+        Self::record(Node::Constant(value), value)
+    }
+
+    fn sqrt(self) -> Self
+    {
+        self.unary(UnaryOperation::Sqrt)
+    }
+
+    fn sin(self) -> Self
+    {
+        self.unary(UnaryOperation::Sin)
+    }
+
+    fn cos(self) -> Self
+    {
+        self.unary(UnaryOperation::Cos)
+    }
+
+    fn tan(self) -> Self
+    {
+        self.unary(UnaryOperation::Tan)
+    }
+
+    fn ln(self) -> Self
+    {
+        self.unary(UnaryOperation::Ln)
+    }
+
+    fn exp(self) -> Self
+    {
+        self.unary(UnaryOperation::Exp)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        Self::record(
+            Node::Powf(self.node, exponent),
+            math::powf(self.value, exponent),
+        )
+    }
+}
+
+impl Real for Tracer
+{
+    fn value(&self) -> f64
+    {
+        self.value
+    }
+}
+
+pub fn trace<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+) -> ExpressionGraph
+{
+    GRAPH.with(|graph| *graph.borrow_mut() = ExpressionGraph::new(VARIABLE_COUNT));
+
+    let mut variables = [Tracer {
+        value: 0.0,
+        node: 0,
+    }; VARIABLE_COUNT];
+    for (input_index, (variable, value)) in variables.iter_mut().zip(x).enumerate() {
+        *variable = Tracer::record(Node::Input(input_index), value);
+    }
+    let outputs = objective.call(variables);
+
+    let mut graph = GRAPH.with(|graph| graph.take());
+    graph.outputs = outputs.iter().map(|output| output.node).collect();
+    graph
+}
@@ -1,26 +1,27 @@
+use crate::math;
 use crate::variable::Variable;
 
 pub fn sin(variable: Variable) -> Variable
 {
     Variable {
-        value: variable.value.sin(),
-        derivative: variable.derivative * variable.value.cos(),
+        value: math::sin(variable.value),
+        derivative: variable.derivative * math::cos(variable.value),
     }
 }
 
 pub fn cos(variable: Variable) -> Variable
 {
     Variable {
-        value: variable.value.cos(),
-        derivative: -variable.derivative * variable.value.sin(),
+        value: math::cos(variable.value),
+        derivative: -variable.derivative * math::sin(variable.value),
     }
 }
 
 pub fn tan(variable: Variable) -> Variable
 {
-    let sec = 1.0 / variable.value.cos();
+    let sec = 1.0 / math::cos(variable.value);
     Variable {
-        value: variable.value.tan(),
+        value: math::tan(variable.value),
         derivative: variable.derivative * sec * sec,
     }
 }
@@ -28,14 +29,14 @@ pub fn tan(variable: Variable) -> Variable
 pub fn ln(variable: Variable) -> Variable
 {
     Variable {
-        value: variable.value.ln(),
+        value: math::ln(variable.value),
         derivative: variable.derivative / variable.value,
     }
 }
 
 pub fn exp(variable: Variable) -> Variable
 {
-    let exp_value = variable.value.exp();
+    let exp_value = math::exp(variable.value);
     Variable {
         value: exp_value,
         derivative: exp_value * variable.derivative,
@@ -52,11 +53,32 @@ pub fn pow(variable: Variable, exponent: f64) -> Variable
     variable.pow(exponent)
 }
 
+// The derivative at 0 is only infinite when x is actually moving, a constant zero stays constant.
 pub fn sqrt(variable: Variable) -> Variable
 {
-    let root = variable.value.sqrt();
+    let root = math::sqrt(variable.value);
     Variable {
         value: root,
-        derivative: variable.derivative / (2.0 * root),
+        derivative: if variable.derivative == 0.0 {
+            0.0
+        } else {
+            variable.derivative / (2.0 * root)
+        },
+    }
+}
+
+// |x| is not differentiable at 0, the derivative there is 0, the midpoint of the subgradient.
+pub fn abs(variable: Variable) -> Variable
+{
+    let sign = if variable.value > 0.0 {
+        1.0
+    } else if variable.value < 0.0 {
+        -1.0
+    } else {
+        0.0
+    };
+    Variable {
+        value: variable.value.abs(),
+        derivative: sign * variable.derivative,
     }
 }
@@ -0,0 +1,65 @@
+use super::linalg::*;
+use super::variable::*;
+
+pub type ImplicitFunction<const STATE_COUNT: usize, const PARAMETER_COUNT: usize> =
+    fn(
+        state: [Variable; STATE_COUNT],
+        parameters: [Variable; PARAMETER_COUNT],
+    ) -> [Variable; STATE_COUNT];
+
+fn make_constants<const COUNT: usize>(values: [f64; COUNT]) -> [Variable; COUNT]
+{
+    values.map(|value| Variable::with_derivative(value, 0.0))
+}
+
+// Sensitivity of a converged solution g(x*, p) = 0 with respect to the parameters, using the
+// implicit function theorem: dx*/dp = -(dg/dx)^-1 dg/dp. Returns None if dg/dx is singular.
+pub fn implicit_jacobian<const STATE_COUNT: usize, const PARAMETER_COUNT: usize>(
+    function: ImplicitFunction<STATE_COUNT, PARAMETER_COUNT>,
+    state: [f64; STATE_COUNT],
+    parameters: [f64; PARAMETER_COUNT],
+) -> Option<[[f64; PARAMETER_COUNT]; STATE_COUNT]>
+{
+    let mut state_jacobian = [[0.0; STATE_COUNT]; STATE_COUNT];
+    let mut parameter_jacobian = [[0.0; PARAMETER_COUNT]; STATE_COUNT];
+
+    for input_index in 0..STATE_COUNT {
+        let mut variables = make_constants(state);
+        variables[input_index].derivative = 1.0;
+        let res = function(variables, make_constants(parameters));
+        for (output_index, output) in res.iter().enumerate() {
+            state_jacobian[output_index][input_index] = output.derivative;
+        }
+    }
+
+    for input_index in 0..PARAMETER_COUNT {
+        let mut variables = make_constants(parameters);
+        variables[input_index].derivative = 1.0;
+        let res = function(make_constants(state), variables);
+        for (output_index, output) in res.iter().enumerate() {
+            parameter_jacobian[output_index][input_index] = -output.derivative;
+        }
+    }
+
+    let solution = Matrix(state_jacobian).solve_matrix(Matrix(parameter_jacobian))?;
+    Some(solution.0)
+}
+
+// Attaches derivatives to a converged solution, so that the solve looks like any other
+// differentiable operation to the surrounding objective.
+pub fn implicit_solution<const STATE_COUNT: usize, const PARAMETER_COUNT: usize>(
+    function: ImplicitFunction<STATE_COUNT, PARAMETER_COUNT>,
+    state: [f64; STATE_COUNT],
+    parameters: [Variable; PARAMETER_COUNT],
+) -> Option<[Variable; STATE_COUNT]>
+{
+    let jacobian = implicit_jacobian(function, state, parameters.map(|p| p.value))?;
+
+    let mut solution = make_constants(state);
+    for (variable, row) in solution.iter_mut().zip(jacobian) {
+        for (sensitivity, parameter) in row.into_iter().zip(parameters) {
+            variable.derivative += sensitivity * parameter.derivative;
+        }
+    }
+    Some(solution)
+}
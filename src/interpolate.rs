@@ -0,0 +1,165 @@
+use super::variable::*;
+
+#[derive(Debug, Copy, Clone)]
+pub enum Boundary
+{
+    Natural,
+    Clamped
+    {
+        start_slope: f64,
+        end_slope: f64,
+    },
+}
+
+fn constants(values: &[f64]) -> Vec<Variable>
+{
+    values
+        .iter()
+        .map(|&value| Variable::with_derivative(value, 0.0))
+        .collect()
+}
+
+fn check_samples(xs: &[f64], ys: &[Variable])
+{
+    assert!(xs.len() >= 2, "interpolation requires at least two samples");
+    assert_eq!(xs.len(), ys.len());
+    assert!(
+        xs.windows(2).all(|pair| pair[0] < pair[1]),
+        "sample positions must be strictly increasing"
+    );
+}
+
+// Index of the segment [xs[i], xs[i + 1]] containing x. Points outside the table use the first or
+// last segment, so interpolants extrapolate with their end pieces.
+fn segment(xs: &[f64], x: f64) -> usize
+{
+    let upper = xs.partition_point(|&sample| sample <= x);
+    upper.clamp(1, xs.len() - 1) - 1
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearInterpolant
+{
+    xs: Vec<f64>,
+    ys: Vec<Variable>,
+}
+
+impl LinearInterpolant
+{
+    pub fn new(xs: &[f64], ys: &[f64]) -> Self
+    {
+        Self::with_variables(xs, &constants(ys))
+    }
+
+    // Table values given as variables carry their derivatives through every evaluation.
+    pub fn with_variables(xs: &[f64], ys: &[Variable]) -> Self
+    {
+        check_samples(xs, ys);
+        Self {
+            xs: xs.to_vec(),
+            ys: ys.to_vec(),
+        }
+    }
+
+    pub fn at(&self, x: Variable) -> Variable
+    {
+        let i = segment(&self.xs, x.value);
+        let h = self.xs[i + 1] - self.xs[i];
+        let t = (x - self.xs[i]) / h;
+        self.ys[i] + t * (self.ys[i + 1] - self.ys[i])
+    }
+
+    pub fn value(&self, x: f64) -> f64
+    {
+        self.at(Variable::with_derivative(x, 0.0)).value
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CubicSpline
+{
+    xs: Vec<f64>,
+    ys: Vec<Variable>,
+    second_derivatives: Vec<Variable>,
+}
+
+impl CubicSpline
+{
+    pub fn new(xs: &[f64], ys: &[f64], boundary: Boundary) -> Self
+    {
+        Self::with_variables(xs, &constants(ys), boundary)
+    }
+
+    // The spline coefficients are linear in the table values, so building the spline from
+    // variables gives the derivative with respect to the table values as well.
+    pub fn with_variables(xs: &[f64], ys: &[Variable], boundary: Boundary) -> Self
+    {
+        check_samples(xs, ys);
+        let n = xs.len();
+        let h: Vec<f64> = xs.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        let slopes: Vec<Variable> = (0..n - 1).map(|i| (ys[i + 1] - ys[i]) / h[i]).collect();
+
+        let mut lower = vec![0.0; n];
+        let mut diagonal = vec![1.0; n];
+        let mut upper = vec![0.0; n];
+        let mut rhs = vec![Variable::empty(); n];
+
+        for i in 1..n - 1 {
+            lower[i] = h[i - 1];
+            diagonal[i] = 2.0 * (h[i - 1] + h[i]);
+            upper[i] = h[i];
+            rhs[i] = 6.0 * (slopes[i] - slopes[i - 1]);
+        }
+
+        if let Boundary::Clamped {
+            start_slope,
+            end_slope,
+        } = boundary
+        {
+            diagonal[0] = 2.0 * h[0];
+            upper[0] = h[0];
+            rhs[0] = 6.0 * (slopes[0] - start_slope);
+            lower[n - 1] = h[n - 2];
+            diagonal[n - 1] = 2.0 * h[n - 2];
+            rhs[n - 1] = 6.0 * (end_slope - slopes[n - 2]);
+        }
+
+        // Thomas algorithm, the matrix only depends on the sample positions.
+        for i in 1..n {
+            let factor = lower[i] / diagonal[i - 1];
+            diagonal[i] -= factor * upper[i - 1];
+            rhs[i] = rhs[i] - factor * rhs[i - 1];
+        }
+        let mut second_derivatives = vec![Variable::empty(); n];
+        second_derivatives[n - 1] = rhs[n - 1] / diagonal[n - 1];
+        for i in (0..n - 1).rev() {
+            second_derivatives[i] = (rhs[i] - upper[i] * second_derivatives[i + 1]) / diagonal[i];
+        }
+
+        Self {
+            xs: xs.to_vec(),
+            ys: ys.to_vec(),
+            second_derivatives,
+        }
+    }
+
+    pub fn at(&self, x: Variable) -> Variable
+    {
+        let i = segment(&self.xs, x.value);
+        let h = self.xs[i + 1] - self.xs[i];
+        let left = self.xs[i + 1] - x;
+        let right = x - self.xs[i];
+        let m0 = self.second_derivatives[i];
+        let m1 = self.second_derivatives[i + 1];
+
+        m0 * left * left * left / (6.0 * h)
+            + m1 * right * right * right / (6.0 * h)
+            + (self.ys[i] / h - m0 * (h / 6.0)) * left
+            + (self.ys[i + 1] / h - m1 * (h / 6.0)) * right
+    }
+
+    pub fn value(&self, x: f64) -> f64
+    {
+        self.at(Variable::with_derivative(x, 0.0)).value
+    }
+}
@@ -0,0 +1,832 @@
+use core::f64::consts::{FRAC_PI_2, PI};
+use core::fmt;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::custom::{ChainRule, CustomFn};
+use crate::math;
+use crate::scalar::Scalar;
+use crate::variable::Variable;
+
+// Results of the elementary functions are widened by this many ulps on each side. The std and libm
+// implementations are accurate to within one ulp, the second covers the rounding of the result.
+const FUNCTION_ULPS: usize = 2;
+
+fn down(x: f64, ulps: usize) -> f64
+{
+    (0..ulps).fold(x, |x, _| x.next_down())
+}
+
+fn up(x: f64, ulps: usize) -> f64
+{
+    (0..ulps).fold(x, |x, _| x.next_up())
+}
+
+// Closed interval [lower, upper] which encloses a set of reals. Every operation rounds outwards, so
+// the result of an expression in intervals contains the exact result for every choice of reals
+// from the operands. Empty results, e.g. the square root of [-2, -1], have NaN bounds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interval
+{
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Interval
+{
+    pub const ENTIRE: Interval = Interval {
+        lower: f64::NEG_INFINITY,
+        upper: f64::INFINITY,
+    };
+
+    pub const EMPTY: Interval = Interval {
+        lower: f64::NAN,
+        upper: f64::NAN,
+    };
+
+    pub fn new(lower: f64, upper: f64) -> Self
+    {
+        assert!(
+            lower <= upper,
+            "interval [{}, {}] is reversed",
+            lower,
+            upper
+        );
+        Self { lower, upper }
+    }
+
+    pub fn point(value: f64) -> Self
+    {
+        Self {
+            lower: value,
+            upper: value,
+        }
+    }
+
+    // Widens a computed [lower, upper] by the given number of ulps on each side.
+    fn rounded(lower: f64, upper: f64, ulps: usize) -> Self
+    {
+        Self {
+            lower: down(lower, ulps),
+            upper: up(upper, ulps),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.lower.is_nan() || self.upper.is_nan()
+    }
+
+    pub fn width(&self) -> f64
+    {
+        up(self.upper - self.lower, 1)
+    }
+
+    // Midpoint rounded to nearest, always inside the interval.
+    pub fn midpoint(&self) -> f64
+    {
+        if self.lower == f64::NEG_INFINITY && self.upper == f64::INFINITY {
+            return 0.0;
+        }
+        let midpoint = self.lower + 0.5 * (self.upper - self.lower);
+        if midpoint.is_finite() {
+            midpoint.clamp(self.lower, self.upper)
+        } else {
+            0.5 * self.lower + 0.5 * self.upper
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool
+    {
+        self.lower <= value && value <= self.upper
+    }
+
+    pub fn contains_zero(&self) -> bool
+    {
+        self.contains(0.0)
+    }
+
+    // Whether other lies inside this interval without touching its bounds.
+    pub fn contains_interior(&self, other: &Interval) -> bool
+    {
+        self.lower < other.lower && other.upper < self.upper
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Interval
+    {
+        let lower = self.lower.max(other.lower);
+        let upper = self.upper.min(other.upper);
+        if self.is_empty() || other.is_empty() || lower > upper {
+            Self::EMPTY
+        } else {
+            Self { lower, upper }
+        }
+    }
+
+    pub fn hull(&self, other: &Interval) -> Interval
+    {
+        Self {
+            lower: self.lower.min(other.lower),
+            upper: self.upper.max(other.upper),
+        }
+    }
+
+    pub fn bisect(&self) -> (Interval, Interval)
+    {
+        self.split(self.midpoint())
+    }
+
+    pub fn split(&self, point: f64) -> (Interval, Interval)
+    {
+        let point = point.clamp(self.lower, self.upper);
+        (
+            Self {
+                lower: self.lower,
+                upper: point,
+            },
+            Self {
+                lower: point,
+                upper: self.upper,
+            },
+        )
+    }
+
+    pub fn abs(&self) -> Interval
+    {
+        if self.lower >= 0.0 {
+            *self
+        } else if self.upper <= 0.0 {
+            -*self
+        } else {
+            Self {
+                lower: 0.0,
+                upper: self.upper.max(-self.lower),
+            }
+        }
+    }
+
+    pub fn square(&self) -> Interval
+    {
+        let magnitude = self.abs();
+        magnitude * magnitude
+    }
+
+    pub fn sqrt(&self) -> Interval
+    {
+        if self.is_empty() || self.upper < 0.0 {
+            return Self::EMPTY;
+        }
+        let lower = math::sqrt(self.lower.max(0.0));
+        Self::rounded(lower, math::sqrt(self.upper), 1).clamp_below(0.0)
+    }
+
+    pub fn exp(&self) -> Interval
+    {
+        Self::rounded(math::exp(self.lower), math::exp(self.upper), FUNCTION_ULPS).clamp_below(0.0)
+    }
+
+    pub fn ln(&self) -> Interval
+    {
+        if self.is_empty() || self.upper <= 0.0 {
+            return Self::EMPTY;
+        }
+        let lower = if self.lower <= 0.0 {
+            f64::NEG_INFINITY
+        } else {
+            math::ln(self.lower)
+        };
+        Self::rounded(lower, math::ln(self.upper), FUNCTION_ULPS)
+    }
+
+    pub fn sin(&self) -> Interval
+    {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        if self.upper - self.lower >= 2.0 * PI {
+            return Self::new(-1.0, 1.0);
+        }
+        let (first, last) = (math::sin(self.lower), math::sin(self.upper));
+        let mut range = Self::rounded(first.min(last), first.max(last), FUNCTION_ULPS);
+        // Maxima at π/2 + 2kπ and minima at -π/2 + 2kπ.
+        if self.contains_period_point(FRAC_PI_2, 2.0 * PI) {
+            range.upper = 1.0;
+        }
+        if self.contains_period_point(-FRAC_PI_2, 2.0 * PI) {
+            range.lower = -1.0;
+        }
+        range.clamp_to(-1.0, 1.0)
+    }
+
+    // cos x = sin(x + π/2), with π/2 enclosed so the shift is rigorous.
+    pub fn cos(&self) -> Interval
+    {
+        let half_pi = Self {
+            lower: FRAC_PI_2.next_down(),
+            upper: FRAC_PI_2.next_up(),
+        };
+        (*self + half_pi).sin()
+    }
+
+    pub fn tan(&self) -> Interval
+    {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        if self.upper - self.lower >= PI || self.contains_period_point(FRAC_PI_2, PI) {
+            return Self::ENTIRE;
+        }
+        Self::rounded(math::tan(self.lower), math::tan(self.upper), FUNCTION_ULPS)
+    }
+
+    // Integer exponents are evaluated by repeated squaring of point intervals at the bounds, which
+    // keeps the enclosure tight, e.g. [-1, 2]² is [0, 4] and not [-2, 4].
+    pub fn powi(&self, exponent: i32) -> Interval
+    {
+        let power = if exponent.unsigned_abs().is_multiple_of(2) {
+            let magnitude = self.abs();
+            Self {
+                lower: point_power(magnitude.lower, exponent.unsigned_abs()).lower,
+                upper: point_power(magnitude.upper, exponent.unsigned_abs()).upper,
+            }
+        } else {
+            Self {
+                lower: point_power(self.lower, exponent.unsigned_abs()).lower,
+                upper: point_power(self.upper, exponent.unsigned_abs()).upper,
+            }
+        };
+        if exponent < 0 {
+            Self::point(1.0) / power
+        } else {
+            power
+        }
+    }
+
+    // Non-integer exponents are only defined for the non-negative part.
+    pub fn powf(&self, exponent: f64) -> Interval
+    {
+        if exponent % 1.0 == 0.0 && exponent.abs() <= i32::MAX as f64 {
+            return self.powi(exponent as i32);
+        }
+        if self.is_empty() || self.upper < 0.0 {
+            return Self::EMPTY;
+        }
+        let base = Self {
+            lower: self.lower.max(0.0),
+            upper: self.upper,
+        };
+        (Self::point(exponent) * base.ln()).exp()
+    }
+
+    // Conservatively whether offset + k·period lies in the interval for some integer k, may answer
+    // true for points just outside.
+    fn contains_period_point(&self, offset: f64, period: f64) -> bool
+    {
+        let first = (self.lower - offset) / period;
+        let last = (self.upper - offset) / period;
+        let slack = 1e-9 * (1.0 + first.abs().max(last.abs()));
+        math::ceil(first - slack) <= math::floor(last + slack)
+    }
+
+    fn clamp_below(mut self, lower: f64) -> Interval
+    {
+        self.lower = self.lower.max(lower);
+        self
+    }
+
+    fn clamp_to(mut self, lower: f64, upper: f64) -> Interval
+    {
+        self.lower = self.lower.max(lower);
+        self.upper = self.upper.min(upper);
+        self
+    }
+}
+
+fn point_power(base: f64, exponent: u32) -> Interval
+{
+    let mut result = Interval::point(1.0);
+    let mut power = Interval::point(base);
+    let mut remaining = exponent;
+    while remaining > 0 {
+        if remaining % 2 == 1 {
+            result = result * power;
+        }
+        power = power * power;
+        remaining /= 2;
+    }
+    result
+}
+
+// Products with a zero factor are exactly zero, also for infinite bounds.
+fn product(a: f64, b: f64) -> f64
+{
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+impl fmt::Display for Interval
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match f.precision() {
+            Some(precision) => write!(
+                f,
+                "[{:.*}, {:.*}]",
+                precision, self.lower, precision, self.upper
+            ),
+            None => write!(f, "[{}, {}]", self.lower, self.upper),
+        }
+    }
+}
+
+impl From<f64> for Interval
+{
+    fn from(value: f64) -> Self
+    {
+        Self::point(value)
+    }
+}
+
+impl Add for Interval
+{
+    type Output = Interval;
+
+    fn add(self, other: Interval) -> Interval
+    {
+        Interval::rounded(self.lower + other.lower, self.upper + other.upper, 1)
+    }
+}
+
+impl Sub for Interval
+{
+    type Output = Interval;
+
+    fn sub(self, other: Interval) -> Interval
+    {
+        Interval::rounded(self.lower - other.upper, self.upper - other.lower, 1)
+    }
+}
+
+impl Neg for Interval
+{
+    type Output = Interval;
+
+    fn neg(self) -> Interval
+    {
+        Interval {
+            lower: -self.upper,
+            upper: -self.lower,
+        }
+    }
+}
+
+impl Mul for Interval
+{
+    type Output = Interval;
+
+    fn mul(self, other: Interval) -> Interval
+    {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+        let products = [
+            product(self.lower, other.lower),
+            product(self.lower, other.upper),
+            product(self.upper, other.lower),
+            product(self.upper, other.upper),
+        ];
+        let lower = products.into_iter().fold(f64::INFINITY, f64::min);
+        let upper = products.into_iter().fold(f64::NEG_INFINITY, f64::max);
+        // A product of exact zeros stays exact.
+        if lower == 0.0 && upper == 0.0 {
+            return Interval::point(0.0);
+        }
+        Interval::rounded(lower, upper, 1)
+    }
+}
+
+// Division by an interval containing zero gives the entire real line.
+impl Div for Interval
+{
+    type Output = Interval;
+
+    fn div(self, other: Interval) -> Interval
+    {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+        if other.contains_zero() {
+            return Interval::ENTIRE;
+        }
+        let quotients = [
+            self.lower / other.lower,
+            self.lower / other.upper,
+            self.upper / other.lower,
+            self.upper / other.upper,
+        ];
+        let lower = quotients.into_iter().fold(f64::INFINITY, f64::min);
+        let upper = quotients.into_iter().fold(f64::NEG_INFINITY, f64::max);
+        Interval::rounded(lower, upper, 1)
+    }
+}
+
+// Constants are taken as the exact value of the f64, T::constant(0.1) is the double nearest to 0.1.
+impl ChainRule for Interval
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        function.value_enclosure(self)
+    }
+}
+
+impl Scalar for Interval
+{
+    fn constant(value: f64) -> Self
+    {
+        Interval::point(value)
+    }
+
+    fn sqrt(self) -> Self
+    {
+        Interval::sqrt(&self)
+    }
+
+    fn sin(self) -> Self
+    {
+        Interval::sin(&self)
+    }
+
+    fn cos(self) -> Self
+    {
+        Interval::cos(&self)
+    }
+
+    fn tan(self) -> Self
+    {
+        Interval::tan(&self)
+    }
+
+    fn ln(self) -> Self
+    {
+        Interval::ln(&self)
+    }
+
+    fn exp(self) -> Self
+    {
+        Interval::exp(&self)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        Interval::powf(&self, exponent)
+    }
+}
+
+// Dual numbers over intervals: value encloses f and derivative encloses f′ over every point of
+// the input intervals.
+impl Variable<Interval>
+{
+    pub fn seeded_interval(value: Interval) -> Self
+    {
+        Self {
+            value,
+            derivative: Interval::point(1.0),
+        }
+    }
+
+    pub fn constant_interval(value: Interval) -> Self
+    {
+        Self {
+            value,
+            derivative: Interval::point(0.0),
+        }
+    }
+
+    // Chain rule which keeps an exactly zero incoming derivative zero, like chain does for f64.
+    fn chain(self, value: Interval, local_derivative: Interval) -> Self
+    {
+        let zero = Interval::point(0.0);
+        Self {
+            value,
+            derivative: if self.derivative == zero {
+                zero
+            } else {
+                self.derivative * local_derivative
+            },
+        }
+    }
+}
+
+impl Add for Variable<Interval>
+{
+    type Output = Variable<Interval>;
+
+    fn add(self, other: Variable<Interval>) -> Variable<Interval>
+    {
+        Variable {
+            value: self.value + other.value,
+            derivative: self.derivative + other.derivative,
+        }
+    }
+}
+
+impl Sub for Variable<Interval>
+{
+    type Output = Variable<Interval>;
+
+    fn sub(self, other: Variable<Interval>) -> Variable<Interval>
+    {
+        Variable {
+            value: self.value - other.value,
+            derivative: self.derivative - other.derivative,
+        }
+    }
+}
+
+impl Neg for Variable<Interval>
+{
+    type Output = Variable<Interval>;
+
+    fn neg(self) -> Variable<Interval>
+    {
+        Variable {
+            value: -self.value,
+            derivative: -self.derivative,
+        }
+    }
+}
+
+impl Mul for Variable<Interval>
+{
+    type Output = Variable<Interval>;
+
+    fn mul(self, other: Variable<Interval>) -> Variable<Interval>
+    {
+        Variable {
+            value: self.value * other.value,
+            derivative: self.derivative * other.value + self.value * other.derivative,
+        }
+    }
+}
+
+impl Div for Variable<Interval>
+{
+    type Output = Variable<Interval>;
+
+    fn div(self, other: Variable<Interval>) -> Variable<Interval>
+    {
+        let value = self.value / other.value;
+        Variable {
+            value,
+            derivative: (self.derivative - value * other.derivative) / other.value,
+        }
+    }
+}
+
+impl ChainRule for Variable<Interval>
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        self.chain(
+            function.value_enclosure(self.value),
+            function.derivative_enclosure(self.value),
+        )
+    }
+}
+
+impl Scalar for Variable<Interval>
+{
+    fn constant(value: f64) -> Self
+    {
+        Variable::constant_interval(Interval::point(value))
+    }
+
+    fn sqrt(self) -> Self
+    {
+        let root = self.value.sqrt();
+        self.chain(root, Interval::point(1.0) / (Interval::point(2.0) * root))
+    }
+
+    fn sin(self) -> Self
+    {
+        self.chain(self.value.sin(), self.value.cos())
+    }
+
+    fn cos(self) -> Self
+    {
+        self.chain(self.value.cos(), -self.value.sin())
+    }
+
+    fn tan(self) -> Self
+    {
+        let tangent = self.value.tan();
+        self.chain(tangent, Interval::point(1.0) + tangent.square())
+    }
+
+    fn ln(self) -> Self
+    {
+        self.chain(self.value.ln(), Interval::point(1.0) / self.value)
+    }
+
+    fn exp(self) -> Self
+    {
+        let exponential = self.value.exp();
+        self.chain(exponential, exponential)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        if exponent == 0.0 {
+            return Self::constant(1.0);
+        }
+        if exponent == 1.0 {
+            return self;
+        }
+        self.chain(
+            self.value.powf(exponent),
+            Interval::point(exponent) * self.value.powf(exponent - 1.0),
+        )
+    }
+}
+
+// A box found by isolate_roots. If unique is set the box contains exactly one root of the system,
+// otherwise it could not be excluded before reaching the tolerance and may contain none, one or
+// several.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RootBox<const N: usize>
+{
+    pub bounds: [Interval; N],
+    pub unique: bool,
+}
+
+#[cfg(feature = "std")]
+fn widest(bounds: &[Interval]) -> (usize, f64)
+{
+    bounds
+        .iter()
+        .map(Interval::width)
+        .enumerate()
+        .fold((0, 0.0), |widest, (index, width)| {
+            if width > widest.1 {
+                (index, width)
+            } else {
+                widest
+            }
+        })
+}
+
+// Boxes are split slightly off centre, roots at round numbers like the origin would otherwise keep
+// landing on a side where no box can prove them unique.
+#[cfg(feature = "std")]
+const SPLIT_RATIO: f64 = 0.4871;
+
+// Splits the widest side of a box. The halves are not known to hold a unique root anymore.
+#[cfg(feature = "std")]
+fn split<const N: usize>(
+    bounds: [Interval; N],
+    pending: &mut Vec<([Interval; N], bool)>,
+    roots: &mut Vec<RootBox<N>>,
+)
+{
+    let (dimension, _) = widest(&bounds);
+    let side = bounds[dimension];
+    let (left, right) = side.split(side.lower + SPLIT_RATIO * (side.upper - side.lower));
+    if left == bounds[dimension] || right == bounds[dimension] {
+        // Adjacent floats, nothing left to split.
+        roots.push(RootBox {
+            bounds,
+            unique: false,
+        });
+        return;
+    }
+    let mut left_bounds = bounds;
+    let mut right_bounds = bounds;
+    left_bounds[dimension] = left;
+    right_bounds[dimension] = right;
+    pending.push((right_bounds, false));
+    pending.push((left_bounds, false));
+}
+
+// Encloses f and its jacobian over a box, one pass per input like evaluate.
+#[cfg(feature = "std")]
+fn enclose<const N: usize>(
+    objective: &impl crate::evaluate::Objective<N, N>,
+    bounds: [Interval; N],
+) -> ([Interval; N], [[Interval; N]; N])
+{
+    let mut values = [Interval::point(0.0); N];
+    let mut jacobian = [[Interval::point(0.0); N]; N];
+    for input_index in 0..N {
+        let mut variables = bounds.map(Variable::constant_interval);
+        variables[input_index].derivative = Interval::point(1.0);
+        for (output_index, output) in objective.call(variables).into_iter().enumerate() {
+            values[output_index] = output.value;
+            jacobian[output_index][input_index] = output.derivative;
+        }
+    }
+    (values, jacobian)
+}
+
+// Krawczyk operator K(X) = m - Y f(m) + (I - Y J(X)) (X - m), with m the midpoint of the box, J(X)
+// an enclosure of the jacobian over it and Y the inverse of the jacobian at m. Every root in X is
+// in K(X), and K(X) inside the interior of X proves there is exactly one. None if the jacobian at
+// the midpoint is singular.
+#[cfg(feature = "std")]
+fn krawczyk<const N: usize>(
+    objective: &impl crate::evaluate::Objective<N, N>,
+    bounds: [Interval; N],
+    jacobian: [[Interval; N]; N],
+) -> Option<[Interval; N]>
+{
+    use crate::linalg::Matrix;
+
+    let midpoint = bounds.map(|bound| bound.midpoint());
+    let midpoint_jacobian = Matrix(jacobian.map(|row| row.map(|entry| entry.midpoint())));
+    let inverse = midpoint_jacobian.inverse()?.0;
+    let midpoint_values = objective.call(midpoint.map(Interval::point));
+
+    let offsets: [Interval; N] = core::array::from_fn(|j| bounds[j] - Interval::point(midpoint[j]));
+    Some(core::array::from_fn(|i| {
+        let mut result = Interval::point(midpoint[i]);
+        for (k, &value) in midpoint_values.iter().enumerate() {
+            result = result - Interval::point(inverse[i][k]) * value;
+        }
+        for (j, &offset) in offsets.iter().enumerate() {
+            // Entry (i, j) of I - Y J(X).
+            let mut entry = Interval::point(if i == j { 1.0 } else { 0.0 });
+            for (k, row) in jacobian.iter().enumerate() {
+                entry = entry - Interval::point(inverse[i][k]) * row[j];
+            }
+            result = result + entry * offset;
+        }
+        result
+    }))
+}
+
+// Isolates the roots of a square system inside a box with the interval Newton method in Krawczyk
+// form. Boxes where f cannot vanish are discarded, the others are contracted with the Krawczyk
+// operator or bisected until they are proven to hold a unique root or are narrower than the
+// tolerance. Every root in the domain lies in one of the returned boxes. The domain has to be
+// bounded.
+#[cfg(feature = "std")]
+pub fn isolate_roots<const N: usize>(
+    objective: &impl crate::evaluate::Objective<N, N>,
+    domain: [Interval; N],
+    tolerance: f64,
+) -> Vec<RootBox<N>>
+{
+    assert!(tolerance > 0.0);
+    assert!(domain
+        .iter()
+        .all(|bound| bound.lower.is_finite() && bound.upper.is_finite()));
+
+    let mut roots = Vec::new();
+    let mut pending = vec![(domain, false)];
+    while let Some((bounds, unique)) = pending.pop() {
+        let (values, jacobian) = enclose(objective, bounds);
+        if values.iter().any(|value| !value.contains_zero()) {
+            continue;
+        }
+
+        let (_, width) = widest(&bounds);
+        let Some(contracted) = krawczyk(objective, bounds, jacobian) else {
+            if width < tolerance {
+                roots.push(RootBox { bounds, unique });
+            } else {
+                split(bounds, &mut pending, &mut roots);
+            }
+            continue;
+        };
+
+        // All roots in the box are in the contracted box, so once it is proven unique it stays
+        // unique for the boxes that follow.
+        let unique = unique
+            || bounds
+                .iter()
+                .zip(&contracted)
+                .all(|(bound, contracted)| bound.contains_interior(contracted));
+        let intersection: [Interval; N] =
+            core::array::from_fn(|i| bounds[i].intersection(&contracted[i]));
+        if intersection.iter().any(Interval::is_empty) {
+            continue;
+        }
+
+        let (_, contracted_width) = widest(&intersection);
+        if contracted_width < tolerance || (unique && contracted_width >= 0.9 * width) {
+            // Narrow enough, or a unique root where contraction stalled at the rounding limit.
+            roots.push(RootBox {
+                bounds: intersection,
+                unique,
+            });
+        } else if contracted_width < 0.9 * width {
+            pending.push((intersection, unique));
+        } else {
+            split(intersection, &mut pending, &mut roots);
+        }
+    }
+    roots
+}
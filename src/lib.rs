@@ -1,5 +1,42 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+// Without std only the forward mode core is available: variables, scalar functions, evaluate,
+// the fixed size linear algebra and complex numbers. The modules which allocate or keep thread
+// local state need std.
+pub mod backend;
+#[cfg(feature = "std")]
+pub mod batch;
+pub mod checked;
+#[cfg(feature = "std")]
+pub mod codegen;
+pub mod complex;
+pub mod custom;
 pub mod evaluate;
+#[cfg(feature = "std")]
+pub mod expression;
 pub mod functions;
+pub mod implicit;
+#[cfg(feature = "std")]
+pub mod interpolate;
+pub mod interval;
+pub mod linalg;
+pub mod math;
+pub mod named;
+pub mod piecewise;
+#[cfg(feature = "std")]
+pub mod quadrature;
+#[cfg(feature = "std")]
+pub mod report;
+pub mod scalar;
+pub mod sensitivity;
+#[cfg(feature = "std")]
+pub mod sparse;
+#[cfg(feature = "std")]
+pub mod structs;
+pub mod table;
+#[cfg(feature = "std")]
+pub mod tape;
+pub mod uncertainty;
 pub mod variable;
 
 pub fn almost_eq(left: f64, right: f64) -> bool
@@ -13,7 +50,7 @@ pub fn almost_equals<const VARIABLE_COUNT: usize>(
     rhs: [f64; VARIABLE_COUNT],
 ) -> bool
 {
-    for (left, right) in lhs.into_iter().zip(rhs.into_iter()) {
+    for (left, right) in lhs.into_iter().zip(rhs) {
         if !almost_eq(left, right) {
             return false;
         }
@@ -21,6 +58,60 @@ pub fn almost_equals<const VARIABLE_COUNT: usize>(
     true
 }
 
+// Objectives and checks shared between the test modules.
+#[cfg(test)]
+mod fixtures
+{
+    use super::evaluate::*;
+    use super::scalar::*;
+
+    // Every unary primitive plus division and powf, defined for 0 < x0 < π/2 and x0·x1 < π/2.
+    pub struct Hard;
+
+    impl Objective<2, 3> for Hard
+    {
+        fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 3]
+        {
+            [
+                variables[0].sin() + variables[1] * variables[1],
+                variables[0] * variables[1].exp() / variables[1].sqrt(),
+                (variables[0] * variables[1]).cos().ln() - variables[0].tan().powf(1.5),
+            ]
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub struct Chain;
+
+    #[cfg(feature = "serde")]
+    impl Objective<3, 2> for Chain
+    {
+        fn call<T: Scalar>(&self, variables: [T; 3]) -> [T; 2]
+        {
+            [
+                variables[0] * variables[1],
+                variables[1].sin() + variables[2],
+            ]
+        }
+    }
+
+    // Values and jacobian computed some other way agree with the dual backend at x.
+    #[cfg(feature = "std")]
+    pub fn assert_matches_dual<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+        objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+        x: [f64; VARIABLE_COUNT],
+        (values, jacobian): ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT]),
+    )
+    {
+        let (expected_values, expected_jacobian) =
+            super::backend::evaluate_with(objective, x, super::backend::Backend::Dual);
+        assert!(super::almost_equals(values, expected_values));
+        for (row, expected_row) in jacobian.into_iter().zip(expected_jacobian) {
+            assert!(super::almost_equals(row, expected_row));
+        }
+    }
+}
+
 #[cfg(test)]
 mod test_variable
 {
@@ -137,7 +228,7 @@ mod test_variable
         let y = 2.0 / x;
         println!("Variable: {:?}", y);
         assert!(almost_eq(y.value, 0.5));
-        assert!(almost_eq(y.derivative, -0.125));
+        assert!(almost_eq(y.derivative, -0.25));
     }
 }
 
@@ -351,3 +442,2817 @@ mod test_evaluate
         assert!(almost_eq(jacobian[2][1], -f64::sin(2.0)));
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod test_quadrature
+{
+    use super::quadrature::*;
+    use super::variable::*;
+    use super::*;
+
+    fn square_function(variables: [Variable; 1]) -> [Variable; 1]
+    {
+        [variables[0] * variables[0]]
+    }
+
+    fn cubic_function(variables: [Variable; 1]) -> [Variable; 1]
+    {
+        [variables[0] * variables[0] * variables[0]]
+    }
+
+    fn exp_function(variables: [Variable; 1]) -> [Variable; 1]
+    {
+        [super::functions::exp(variables[0])]
+    }
+
+    #[test]
+    fn test_linspace_includes_end()
+    {
+        let range = linspace(0.0, 1.0, 4);
+        assert_eq!(range.len(), 5);
+        assert!(almost_eq(range[0], 0.0));
+        assert!(almost_eq(range[2], 0.5));
+        assert_eq!(range[4], 1.0);
+    }
+
+    #[test]
+    fn test_trapezoidal_linear_exact()
+    {
+        fn linear(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            [2.0 * variables[0] + 1.0]
+        }
+        assert!(almost_eq(trapezoidal(linear, 0.0, 3.0, 7), 12.0));
+    }
+
+    #[test]
+    fn test_hermite_cubic_exact()
+    {
+        let integral = hermite(cubic_function, 0.0, 2.0, 3);
+        assert!(almost_eq(integral, 4.0));
+    }
+
+    #[test]
+    fn test_hermite_beats_trapezoidal()
+    {
+        let exact = f64::exp(1.0) - 1.0;
+        let trapezoidal_error = (trapezoidal(exp_function, 0.0, 1.0, 10) - exact).abs();
+        let hermite_error = (hermite(exp_function, 0.0, 1.0, 10) - exact).abs();
+        assert!(hermite_error < 1.0e-3 * trapezoidal_error);
+    }
+
+    #[test]
+    fn test_simpson_cubic_exact()
+    {
+        assert!(almost_eq(simpson(cubic_function, 0.0, 2.0, 2), 4.0));
+        assert!(almost_eq(
+            simpson(square_function, 0.0, 5.0, 10),
+            125.0 / 3.0
+        ));
+    }
+
+    #[test]
+    fn test_gauss_legendre_nodes()
+    {
+        let nodes = gauss_legendre_nodes(2);
+        let node = 1.0 / f64::sqrt(3.0);
+        assert!(almost_eq(nodes[0].0.abs(), node));
+        assert!(almost_eq(nodes[1].0.abs(), node));
+        assert!(almost_eq(nodes[0].1, 1.0));
+        assert!(almost_eq(nodes[1].1, 1.0));
+    }
+
+    #[test]
+    fn test_gauss_legendre()
+    {
+        assert!(almost_eq(gauss_legendre(cubic_function, 0.0, 2.0, 2), 4.0));
+        let integral = gauss_legendre(exp_function, 0.0, 1.0, 8);
+        assert!(almost_eq(integral, f64::exp(1.0) - 1.0));
+    }
+
+    #[test]
+    fn test_adaptive_simpson()
+    {
+        let estimate = adaptive_simpson(exp_function, 0.0, 1.0, 1.0e-10);
+        let exact = f64::exp(1.0) - 1.0;
+        assert!(almost_eq(estimate.value, exact));
+        assert!(estimate.error < 1.0e-9);
+    }
+
+    #[test]
+    fn test_adaptive_simpson_zero_tolerance()
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        fn counted(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            exp_function(variables)
+        }
+
+        let estimate = adaptive_simpson(counted, 0.0, 1.0, 0.0);
+        assert!(almost_eq(estimate.value, f64::exp(1.0) - 1.0));
+        assert!(CALLS.load(Ordering::Relaxed) < 1 << 16);
+
+        // A kink keeps the difference above rounding, the depth limit stops it.
+        fn kink(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            [super::functions::abs(variables[0] - 1.0 / 3.0)]
+        }
+
+        let estimate = adaptive_simpson(kink, 0.0, 1.0, 0.0);
+        assert!((estimate.value - 5.0 / 18.0).abs() < 1.0e-10);
+    }
+
+    #[test]
+    fn test_leibniz_parameter()
+    {
+        // d/dp of the integral of p * x^2 over [0, 1] is 1 / 3.
+        let p = Variable::seeded(2.0);
+        let start = Variable::with_derivative(0.0, 0.0);
+        let end = Variable::with_derivative(1.0, 0.0);
+        let integral = leibniz(|x| p * x * x, start, end, 10);
+        assert!(almost_eq(integral.value, 2.0 / 3.0));
+        assert!(almost_eq(integral.derivative, 1.0 / 3.0));
+    }
+
+    #[test]
+    fn test_leibniz_bounds()
+    {
+        // d/db of the integral of x^2 over [0, b] is b^2.
+        let start = Variable::with_derivative(0.0, 0.0);
+        let end = Variable::seeded(3.0);
+        let integral = leibniz(|x| x * x, start, end, 10);
+        assert!(almost_eq(integral.value, 9.0));
+        assert!(almost_eq(integral.derivative, 9.0));
+    }
+}
+
+#[cfg(test)]
+mod test_implicit
+{
+    use super::evaluate::*;
+    use super::functions::*;
+    use super::implicit::*;
+    use super::variable::*;
+    use super::*;
+
+    fn square_root_residual(state: [Variable; 1], parameters: [Variable; 1]) -> [Variable; 1]
+    {
+        [square(state[0]) - parameters[0]]
+    }
+
+    fn linear_residual(state: [Variable; 2], parameters: [Variable; 2]) -> [Variable; 2]
+    {
+        [
+            state[0] + state[1] - parameters[0],
+            state[0] - state[1] - parameters[1],
+        ]
+    }
+
+    #[test]
+    fn test_implicit_square_root()
+    {
+        let jacobian = implicit_jacobian(square_root_residual, [2.0], [4.0]).unwrap();
+        assert!(almost_eq(jacobian[0][0], 0.25));
+    }
+
+    #[test]
+    fn test_implicit_linear_system()
+    {
+        let jacobian = implicit_jacobian(linear_residual, [2.0, 1.0], [3.0, 1.0]).unwrap();
+        assert!(almost_equals(jacobian[0], [0.5, 0.5]));
+        assert!(almost_equals(jacobian[1], [0.5, -0.5]));
+    }
+
+    #[test]
+    fn test_implicit_singular()
+    {
+        assert!(implicit_jacobian(square_root_residual, [0.0], [0.0]).is_none());
+    }
+
+    #[test]
+    fn test_implicit_nearly_singular()
+    {
+        // The state columns of dF/dy differ by 1e-14, far below what the solution can resolve.
+        fn residual(state: [Variable; 2], parameters: [Variable; 1]) -> [Variable; 2]
+        {
+            [
+                state[0] + state[1] - parameters[0],
+                state[0] + (1.0 + 1e-14) * state[1] - 2.0 * parameters[0],
+            ]
+        }
+
+        assert!(implicit_jacobian(residual, [1.0, 1.0], [2.0]).is_none());
+
+        // The same threshold is relative, a well conditioned system at a tiny scale still solves.
+        fn scaled(state: [Variable; 1], parameters: [Variable; 1]) -> [Variable; 1]
+        {
+            [1e-14 * state[0] - parameters[0]]
+        }
+
+        let jacobian = implicit_jacobian(scaled, [1.0], [1e-14]).unwrap();
+        assert!(almost_eq(jacobian[0][0], 1e14));
+    }
+
+    #[test]
+    fn test_implicit_solution_inside_objective()
+    {
+        fn objective_function(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            let mut x = 1.0;
+            for _ in 0..50 {
+                x -= (x * x - variables[0].value) / (2.0 * x);
+            }
+            let root = implicit_solution(square_root_residual, [x], variables).unwrap();
+            [3.0 * root[0]]
+        }
+
+        let (values, jacobian) = evaluate(objective_function, [9.0]);
+        assert!(almost_eq(values[0], 9.0));
+        assert!(almost_eq(jacobian[0][0], 0.5));
+    }
+}
+
+#[cfg(test)]
+mod test_custom
+{
+    use super::custom::*;
+    use super::evaluate::*;
+    use super::functions::*;
+    use super::variable::*;
+    use super::*;
+
+    #[derive(Clone)]
+    struct Sinh;
+
+    impl CustomFn for Sinh
+    {
+        fn value(&self, x: f64) -> f64
+        {
+            x.sinh()
+        }
+
+        fn derivative(&self, x: f64) -> f64
+        {
+            x.cosh()
+        }
+    }
+
+    #[test]
+    fn test_custom_trait()
+    {
+        let fx = Sinh.apply(Variable::seeded(1.0));
+        assert!(almost_eq(fx.value, f64::sinh(1.0)));
+        assert!(almost_eq(fx.derivative, f64::cosh(1.0)));
+        assert!(almost_eq(Sinh.apply(1.0), f64::sinh(1.0)));
+    }
+
+    #[test]
+    fn test_custom_unary_chain()
+    {
+        let cube = custom_unary(|x| x * x * x, |x| 3.0 * x * x);
+        let x = Variable::seeded(2.0);
+        let fx = cube.apply(sin(x));
+        assert!(almost_eq(fx.value, f64::sin(2.0).powi(3)));
+        assert!(almost_eq(
+            fx.derivative,
+            3.0 * f64::sin(2.0).powi(2) * f64::cos(2.0)
+        ));
+    }
+
+    #[test]
+    fn test_custom_in_objective()
+    {
+        fn objective_function(variables: [Variable; 2]) -> [Variable; 1]
+        {
+            let table = custom_unary(|x| 2.0 * x + 1.0, |_| 2.0);
+            [table.apply(variables[0]) * variables[1]]
+        }
+
+        let (values, jacobian) = evaluate(objective_function, [1.0, 3.0]);
+        assert!(almost_eq(values[0], 9.0));
+        assert!(almost_equals(jacobian[0], [6.0, 3.0]));
+    }
+
+    // Cube with the enclosures filled in, it is increasing so the endpoints bound it.
+    #[derive(Clone)]
+    struct Cube;
+
+    impl CustomFn for Cube
+    {
+        fn value(&self, x: f64) -> f64
+        {
+            x * x * x
+        }
+
+        fn derivative(&self, x: f64) -> f64
+        {
+            3.0 * x * x
+        }
+
+        fn value_enclosure(&self, x: interval::Interval) -> interval::Interval
+        {
+            x.powi(3)
+        }
+
+        fn derivative_enclosure(&self, x: interval::Interval) -> interval::Interval
+        {
+            interval::Interval::point(3.0) * x.square()
+        }
+    }
+
+    struct Cubic;
+
+    impl Objective<3, 2> for Cubic
+    {
+        fn call<T: scalar::Scalar>(&self, variables: [T; 3]) -> [T; 2]
+        {
+            [
+                Cube.apply(variables[0]) * variables[1],
+                Cube.apply(variables[2]).sin(),
+            ]
+        }
+    }
+
+    const X: [f64; 3] = [1.0, 2.0, 0.5];
+
+    fn assert_cubic(values: [f64; 2], jacobian: [[f64; 3]; 2], tolerance: f64)
+    {
+        let expected_values = [2.0, f64::sin(0.125)];
+        let expected_jacobian = [[6.0, 1.0, 0.0], [0.0, 0.0, 0.75 * f64::cos(0.125)]];
+        for (value, expected) in values.into_iter().zip(expected_values) {
+            assert!((value - expected).abs() < tolerance);
+        }
+        for (row, expected_row) in jacobian.into_iter().zip(expected_jacobian) {
+            for (derivative, expected) in row.into_iter().zip(expected_row) {
+                assert!((derivative - expected).abs() < tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_in_backends()
+    {
+        use super::backend::*;
+
+        let (values, jacobian) = evaluate_with(&Cubic, X, Backend::Dual);
+        assert_cubic(values, jacobian, 1.0e-12);
+        let (values, jacobian) = evaluate_with(&Cubic, X, Backend::complex_step());
+        assert_cubic(values, jacobian, 1.0e-12);
+        let (values, jacobian) = evaluate_with(&Cubic, X, Backend::finite_difference());
+        assert_cubic(values, jacobian, 1.0e-6);
+    }
+
+    #[test]
+    fn test_custom_checked()
+    {
+        use super::checked::*;
+
+        let (values, jacobian) = try_evaluate(&Cubic, X).unwrap();
+        assert_cubic(values, jacobian, 1.0e-12);
+
+        struct Log;
+
+        impl Objective<1, 1> for Log
+        {
+            fn call<T: scalar::Scalar>(&self, variables: [T; 1]) -> [T; 1]
+            {
+                [custom_unary(f64::ln, |x| 1.0 / x).apply(variables[0])]
+            }
+        }
+
+        let error = try_evaluate(&Log, [-1.0]).unwrap_err();
+        assert_eq!(error.primitive, Primitive::Custom);
+        assert_eq!(error.kind, DomainErrorKind::NonFinite);
+    }
+
+    #[test]
+    fn test_custom_interval()
+    {
+        use super::interval::*;
+
+        let bounds = [
+            Interval::new(0.9, 1.1),
+            Interval::point(2.0),
+            Interval::point(0.5),
+        ];
+        let outputs = Cubic.call([
+            Variable::seeded_interval(bounds[0]),
+            Variable::constant_interval(bounds[1]),
+            Variable::constant_interval(bounds[2]),
+        ]);
+        for x0 in [0.9, 1.0, 1.1] {
+            let (values, jacobian) = evaluate_with_dual(&Cubic, [x0, 2.0, 0.5]);
+            assert!(outputs[0].value.contains(values[0]));
+            assert!(outputs[0].derivative.contains(jacobian[0][0]));
+            assert!(outputs[1].value.contains(values[1]));
+        }
+        assert_eq!(outputs[1].derivative, Interval::point(0.0));
+
+        // Without enclosures nothing is known about the value.
+        let unknown = custom_unary(|x| x * x * x, |x| 3.0 * x * x);
+        assert_eq!(unknown.apply(bounds[0]), Interval::ENTIRE);
+    }
+
+    fn evaluate_with_dual(
+        objective: &impl Objective<3, 2>,
+        x: [f64; 3],
+    ) -> ([f64; 2], [[f64; 3]; 2])
+    {
+        super::backend::evaluate_with(objective, x, super::backend::Backend::Dual)
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_custom_sparse()
+    {
+        use super::sparse::*;
+
+        let pattern = detect_sparsity(&Cubic, X);
+        assert_eq!(pattern.rows, vec![vec![0, 1], vec![2]]);
+        let coloring = color_columns(&pattern);
+        let (values, jacobian) = evaluate_sparse(&Cubic, X, &pattern, &coloring);
+        let (_, expected) = evaluate_with_dual(&Cubic, X);
+        assert_eq!(values[0], 2.0);
+        for (row, expected_row) in expected.iter().enumerate() {
+            for (column, &expected) in expected_row.iter().enumerate() {
+                assert!(almost_eq(jacobian.get(row, column), expected));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_custom_tape()
+    {
+        use super::expression::*;
+        use super::tape::*;
+
+        let graph = trace(&Cubic, X);
+        assert_eq!(graph.functions.len(), 2);
+        assert_eq!(graph.formula(graph.outputs[0]), "custom0(x0) * x1");
+
+        // Recorded at X and replayed elsewhere, the custom function is evaluated again.
+        let tape = Tape::<3, 2>::record(&Cubic, X);
+        let mut workspace = tape.workspace();
+        let x = [0.5, -1.0, 2.0];
+        let (values, jacobian) = tape.evaluate(x, &mut workspace);
+        let (expected_values, expected_jacobian) = evaluate_with_dual(&Cubic, x);
+        assert!(almost_equals(values, expected_values));
+        for (row, expected) in jacobian.into_iter().zip(expected_jacobian) {
+            assert!(almost_equals(row, expected));
+        }
+        assert!(almost_equals(
+            graph.evaluate(&x).try_into().unwrap(),
+            expected_values
+        ));
+        assert!(almost_equals(
+            graph.derivative(0).evaluate(&x).try_into().unwrap(),
+            [expected_jacobian[0][0], expected_jacobian[1][0]]
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_custom_codegen()
+    {
+        use super::codegen::*;
+        use super::expression::*;
+
+        let code = generate_rust(&trace(&Cubic, X), "cubic");
+        assert!(code.contains("custom0(x[0])"));
+        assert!(code.contains("custom0_derivative(x[0])"));
+        assert!(code.contains("custom1_derivative(x[2])"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_custom_batch()
+    {
+        use super::batch::*;
+
+        let points = [X, [0.5, -1.0, 2.0], [-2.0, 0.0, 1.0]];
+        for (point, (values, jacobian)) in points.iter().zip(evaluate_batch(&Cubic, &points)) {
+            let (expected_values, expected_jacobian) = evaluate_with_dual(&Cubic, *point);
+            assert_eq!(values, expected_values);
+            assert_eq!(jacobian, expected_jacobian);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "serde"))]
+    fn test_custom_graph_is_not_serializable()
+    {
+        let graph = super::expression::trace(&Cubic, X);
+        assert!(serde_json::to_string(&graph).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_interpolate
+{
+    use super::interpolate::*;
+    use super::variable::*;
+    use super::*;
+
+    #[test]
+    fn test_linear_interpolant()
+    {
+        let interpolant = LinearInterpolant::new(&[0.0, 1.0, 3.0], &[1.0, 3.0, 2.0]);
+        let fx = interpolant.at(Variable::seeded(0.5));
+        assert!(almost_eq(fx.value, 2.0));
+        assert!(almost_eq(fx.derivative, 2.0));
+
+        let fx = interpolant.at(Variable::seeded(2.0));
+        assert!(almost_eq(fx.value, 2.5));
+        assert!(almost_eq(fx.derivative, -0.5));
+        assert!(almost_eq(interpolant.value(3.0), 2.0));
+    }
+
+    #[test]
+    fn test_linear_interpolant_table_derivative()
+    {
+        let ys = [
+            Variable::with_derivative(1.0, 0.0),
+            Variable::seeded(3.0),
+            Variable::with_derivative(2.0, 0.0),
+        ];
+        let interpolant = LinearInterpolant::with_variables(&[0.0, 1.0, 3.0], &ys);
+        let fx = interpolant.at(Variable::with_derivative(0.25, 0.0));
+        assert!(almost_eq(fx.derivative, 0.25));
+    }
+
+    #[test]
+    fn test_natural_spline_reproduces_samples()
+    {
+        let xs = [0.0, 1.0, 2.0, 4.0];
+        let ys = [1.0, 2.0, 0.0, 3.0];
+        let spline = CubicSpline::new(&xs, &ys, Boundary::Natural);
+        for (x, y) in xs.into_iter().zip(ys) {
+            assert!(almost_eq(spline.value(x), y));
+        }
+    }
+
+    #[test]
+    fn test_clamped_spline_reproduces_cubic()
+    {
+        let xs = [0.0, 0.5, 1.5, 2.0, 3.0];
+        let ys = xs.map(|x| x * x * x - x);
+        let boundary = Boundary::Clamped {
+            start_slope: -1.0,
+            end_slope: 26.0,
+        };
+        let spline = CubicSpline::new(&xs, &ys, boundary);
+        let fx = spline.at(Variable::seeded(1.2));
+        assert!(almost_eq(fx.value, 1.2 * 1.2 * 1.2 - 1.2));
+        assert!(almost_eq(fx.derivative, 3.0 * 1.2 * 1.2 - 1.0));
+    }
+
+    #[test]
+    fn test_spline_table_derivative()
+    {
+        let xs = [0.0, 1.0, 2.0, 3.0];
+        let ys = [0.0, 1.0, 0.0, 1.0];
+        let x = Variable::with_derivative(1.3, 0.0);
+        let seeded: Vec<Variable> = ys
+            .iter()
+            .enumerate()
+            .map(|(i, &y)| Variable::with_derivative(y, if i == 2 { 1.0 } else { 0.0 }))
+            .collect();
+        let derivative = CubicSpline::with_variables(&xs, &seeded, Boundary::Natural)
+            .at(x)
+            .derivative;
+
+        let step = 1.0e-6;
+        let mut shifted = ys;
+        shifted[2] += step;
+        let forward = CubicSpline::new(&xs, &shifted, Boundary::Natural).value(x.value);
+        let backward = CubicSpline::new(&xs, &ys, Boundary::Natural).value(x.value);
+        assert!((derivative - (forward - backward) / step).abs() < 1.0e-6);
+    }
+}
+
+#[cfg(test)]
+mod test_linalg
+{
+    use super::evaluate::*;
+    use super::linalg::*;
+    use super::variable::*;
+    use super::*;
+
+    #[test]
+    fn test_vector_operations()
+    {
+        let a = Vector([1.0, 2.0, 2.0]);
+        let b = Vector([3.0, 0.0, -1.0]);
+        assert!(almost_eq(a.dot(&b), 1.0));
+        assert!(almost_eq(a.norm(), 3.0));
+        assert!(almost_equals((a + b).0, [4.0, 2.0, 1.0]));
+        assert!(almost_equals((a - b).0, [-2.0, 2.0, 3.0]));
+        assert!(almost_equals((-a).0, [-1.0, -2.0, -2.0]));
+    }
+
+    #[test]
+    fn test_matrix_product_and_transpose()
+    {
+        let a = Matrix([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b = a * a.transpose();
+        assert!(almost_equals(b.0[0], [14.0, 32.0]));
+        assert!(almost_equals(b.0[1], [32.0, 77.0]));
+
+        let v = a * Vector([1.0, 0.0, -1.0]);
+        assert!(almost_equals(v.0, [-2.0, -2.0]));
+    }
+
+    #[test]
+    fn test_determinant()
+    {
+        let a = Matrix([[0.0, 2.0, 1.0], [1.0, 1.0, 0.0], [3.0, 0.0, 1.0]]);
+        assert!(almost_eq(a.determinant(), -5.0));
+        assert!(almost_eq(Matrix::<3, 3>::identity().determinant(), 1.0));
+        assert!(almost_eq(
+            Matrix([[1.0, 2.0], [2.0, 4.0]]).determinant(),
+            0.0
+        ));
+    }
+
+    #[test]
+    fn test_solve_and_inverse()
+    {
+        let a = Matrix([[2.0, 1.0], [1.0, 3.0]]);
+        let x = a.solve(Vector([3.0, 5.0])).unwrap();
+        assert!(almost_equals(x.0, [0.8, 1.4]));
+
+        let product = a * a.inverse().unwrap();
+        assert!(almost_equals(product.0[0], [1.0, 0.0]));
+        assert!(almost_equals(product.0[1], [0.0, 1.0]));
+        assert!(Matrix([[1.0, 2.0], [2.0, 4.0]]).inverse().is_none());
+    }
+
+    #[test]
+    fn test_differentiate_determinant()
+    {
+        fn objective_function(variables: [Variable; 2]) -> [Variable; 1]
+        {
+            let two = Variable::with_derivative(2.0, 0.0);
+            let matrix = Matrix([[variables[0], two], [variables[1], variables[0]]]);
+            [matrix.determinant()]
+        }
+
+        let (values, jacobian) = evaluate(objective_function, [3.0, 1.0]);
+        assert!(almost_eq(values[0], 7.0));
+        assert!(almost_equals(jacobian[0], [6.0, -2.0]));
+    }
+
+    #[test]
+    fn test_differentiate_determinant_at_singular_points()
+    {
+        fn diagonal(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            let zero = Variable::with_derivative(0.0, 0.0);
+            let one = Variable::with_derivative(1.0, 0.0);
+            [Matrix([[variables[0], zero], [zero, one]]).determinant()]
+        }
+
+        fn ones(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            let one = Variable::with_derivative(1.0, 0.0);
+            [Matrix([[variables[0], one], [one, one]]).determinant()]
+        }
+
+        fn column(variables: [Variable; 2]) -> [Variable; 1]
+        {
+            let one = Variable::with_derivative(1.0, 0.0);
+            [Matrix([[variables[0], one], [variables[1], one]]).determinant()]
+        }
+
+        let (values, jacobian) = evaluate(diagonal, [0.0]);
+        assert_eq!((values[0], jacobian[0][0]), (0.0, 1.0));
+        let (values, jacobian) = evaluate(ones, [1.0]);
+        assert_eq!((values[0], jacobian[0][0]), (0.0, 1.0));
+        let (values, jacobian) = evaluate(column, [0.0, 0.0]);
+        assert_eq!(values[0], 0.0);
+        assert!(almost_equals(jacobian[0], [1.0, -1.0]));
+
+        // Singular 3x3 of rank two, the derivative is the cofactor of the varied entry.
+        fn rank_two(variables: [Variable; 1]) -> [Variable; 1]
+        {
+            let constant = |value| Variable::with_derivative(value, 0.0);
+            let matrix = Matrix([
+                [variables[0], constant(2.0), constant(3.0)],
+                [constant(2.0), constant(4.0), constant(6.0)],
+                [constant(3.0), constant(6.0), constant(9.1)],
+            ]);
+            [matrix.determinant()]
+        }
+
+        let (values, jacobian) = evaluate(rank_two, [1.0]);
+        assert!(values[0].abs() < 1e-12);
+        assert!(almost_eq(jacobian[0][0], 4.0 * 9.1 - 36.0));
+    }
+
+    #[test]
+    fn test_solve_nearly_singular()
+    {
+        let matrix = Matrix([[1.0, 1.0], [1.0, 1.0 + 1e-14]]);
+        assert!(matrix.solve(Vector([1.0, 2.0])).is_none());
+        let scaled = Matrix([[1e-20, 0.0], [0.0, 2e-20]]);
+        assert!(almost_equals(
+            scaled.solve(Vector([1e-20, 1e-20])).unwrap().0,
+            [1.0, 0.5]
+        ));
+    }
+
+    #[test]
+    fn test_differentiate_solve()
+    {
+        fn objective_function(variables: [Variable; 1]) -> [Variable; 2]
+        {
+            let one = Variable::with_derivative(1.0, 0.0);
+            let zero = Variable::with_derivative(0.0, 0.0);
+            let matrix = Matrix([[variables[0], zero], [one, one]]);
+            matrix.solve(Vector([one, one])).unwrap().0
+        }
+
+        let (values, jacobian) = evaluate(objective_function, [2.0]);
+        assert!(almost_equals(values, [0.5, 0.5]));
+        assert!(almost_eq(jacobian[0][0], -0.25));
+        assert!(almost_eq(jacobian[1][0], 0.25));
+    }
+
+    #[test]
+    fn test_evaluate_matrix()
+    {
+        fn objective_function(variables: [Variable; 2]) -> [Variable; 2]
+        {
+            [variables[0] * variables[1], variables[0] + variables[1]]
+        }
+
+        let (values, jacobian) = evaluate_matrix(objective_function, Vector([2.0, 3.0]));
+        assert!(almost_equals(values.0, [6.0, 5.0]));
+        assert!(almost_eq(jacobian[(0, 0)], 3.0));
+        assert!(almost_eq(jacobian[(0, 1)], 2.0));
+        assert!(almost_eq(jacobian[(1, 0)], 1.0));
+        assert!(almost_eq(jacobian[(1, 1)], 1.0));
+    }
+}
+
+#[cfg(test)]
+mod test_complex
+{
+    use super::complex::*;
+    use super::evaluate::*;
+    use super::variable::*;
+    use super::*;
+
+    fn complex_almost_eq(left: Complex, right: Complex) -> bool
+    {
+        almost_eq(left.re, right.re) && almost_eq(left.im, right.im)
+    }
+
+    #[test]
+    fn test_complex_arithmetic()
+    {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+        assert!(complex_almost_eq(a * b, Complex::new(5.0, 5.0)));
+        assert!(complex_almost_eq((a * b) / b, a));
+        assert!(complex_almost_eq(a.ln().exp(), a));
+        assert!(complex_almost_eq(a.sqrt() * a.sqrt(), a));
+        assert!(complex_almost_eq(a.powi(3), a * a * a));
+        assert!(complex_almost_eq(a.powf(3.0), a * a * a));
+    }
+
+    #[test]
+    fn test_roots_and_powers_on_the_axes()
+    {
+        assert_eq!(Complex::new(-1.0, 0.0).sqrt(), Complex::new(0.0, 1.0));
+        assert_eq!(Complex::new(-4.0, -0.0).sqrt(), Complex::new(0.0, -2.0));
+        assert_eq!(Complex::new(9.0, 0.0).sqrt(), Complex::new(3.0, 0.0));
+        assert_eq!(Complex::new(0.0, 2.0).sqrt(), Complex::new(1.0, 1.0));
+        assert_eq!(Complex::new(0.0, 0.0).sqrt(), Complex::new(0.0, 0.0));
+
+        // Square and multiply, a loop over the exponent would not finish.
+        let i = Complex::new(0.0, 1.0);
+        assert_eq!(i.powi(i32::MAX), Complex::new(0.0, -1.0));
+        assert_eq!(i.powi(-3), i);
+    }
+
+    #[test]
+    fn test_powi_derivative_edge_cases()
+    {
+        let zero = ComplexVariable::seeded(Complex::new(0.0, 0.0));
+        let constant = zero.powi(0);
+        assert_eq!(constant.value, Complex::new(1.0, 0.0));
+        assert_eq!(constant.derivative, Complex::new(0.0, 0.0));
+
+        let one = ComplexVariable::seeded(Complex::new(1.0, 0.0));
+        let power = one.powi(i32::MIN);
+        assert_eq!(power.value, Complex::new(1.0, 0.0));
+        assert_eq!(power.derivative, Complex::new(i32::MIN as f64, 0.0));
+    }
+
+    #[test]
+    fn test_polynomial_derivative()
+    {
+        fn cubic(z: ComplexVariable) -> ComplexVariable
+        {
+            z.powi(3) - 1.0
+        }
+
+        let z = Complex::new(1.0, 1.0);
+        let (value, derivative) = evaluate_complex(cubic, z);
+        assert!(complex_almost_eq(value, z * z * z - Complex::from(1.0)));
+        assert!(complex_almost_eq(derivative, 3.0 * z * z));
+    }
+
+    #[test]
+    fn test_elementary_derivatives()
+    {
+        let z = Complex::new(0.5, -0.3);
+        let seeded = ComplexVariable::seeded(z);
+        assert!(complex_almost_eq(seeded.exp().derivative, z.exp()));
+        assert!(complex_almost_eq(seeded.sin().derivative, z.cos()));
+        assert!(complex_almost_eq(seeded.cos().derivative, -z.sin()));
+        assert!(complex_almost_eq(
+            seeded.ln().derivative,
+            Complex::from(1.0) / z
+        ));
+        assert!(complex_almost_eq(
+            seeded.tan().derivative,
+            Complex::from(1.0) / (z.cos() * z.cos())
+        ));
+
+        let exponent = ComplexVariable::constant(Complex::new(2.0, 0.0));
+        assert!(complex_almost_eq(seeded.pow(exponent).derivative, 2.0 * z));
+    }
+
+    #[test]
+    fn test_newton_complex_cubic()
+    {
+        fn cubic(z: ComplexVariable) -> ComplexVariable
+        {
+            z * z * z - 1.0
+        }
+
+        let mut z = Complex::new(-1.0, 1.0);
+        for _ in 0..50 {
+            let (value, derivative) = evaluate_complex(cubic, z);
+            z = z - value / derivative;
+        }
+        let root = Complex::new(-0.5, 0.5 * f64::sqrt(3.0));
+        assert!(complex_almost_eq(z, root));
+    }
+
+    #[test]
+    fn test_matches_real_jacobian()
+    {
+        fn cubic(z: ComplexVariable) -> ComplexVariable
+        {
+            z * z * z - 1.0
+        }
+
+        fn real_cubic(variables: [Variable; 2]) -> [Variable; 2]
+        {
+            let x = variables[0];
+            let y = variables[1];
+            [
+                x * x * x - 3.0 * x * y * y - 1.0,
+                3.0 * x * x * y - y * y * y,
+            ]
+        }
+
+        let z = Complex::new(0.7, -1.2);
+        let (_, jacobian) = evaluate(real_cubic, [z.re, z.im]);
+        let (_, derivative) = evaluate_complex(cubic, z);
+        let complex_jacobian = derivative.to_jacobian();
+        for (row, complex_row) in jacobian.into_iter().zip(complex_jacobian) {
+            assert!(almost_equals(row, complex_row));
+        }
+        assert!(complex_almost_eq(
+            Complex::from_jacobian(jacobian),
+            derivative
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_backend
+{
+    use super::backend::*;
+    use super::evaluate::*;
+    use super::fixtures::*;
+    use super::scalar::*;
+    use super::*;
+
+    const X: [f64; 2] = [0.4, 1.3];
+
+    fn assert_hard_jacobian(values: [f64; 3], jacobian: [[f64; 2]; 3], tolerance: f64)
+    {
+        let [x0, x1] = X;
+        let tan = x0.tan();
+        let expected_values = [
+            x0.sin() + x1 * x1,
+            x0 * x1.exp() / x1.sqrt(),
+            (x0 * x1).cos().ln() - tan.powf(1.5),
+        ];
+        let expected_jacobian = [
+            [x0.cos(), 2.0 * x1],
+            [
+                x1.exp() / x1.sqrt(),
+                x0 * x1.exp() / x1.sqrt() * (1.0 - 0.5 / x1),
+            ],
+            [
+                -x1 * (x0 * x1).tan() - 1.5 * tan.sqrt() * (1.0 + tan * tan),
+                -x0 * (x0 * x1).tan(),
+            ],
+        ];
+        for (value, expected) in values.into_iter().zip(expected_values) {
+            assert!((value - expected).abs() < tolerance);
+        }
+        for (row, expected_row) in jacobian.into_iter().zip(expected_jacobian) {
+            for (derivative, expected) in row.into_iter().zip(expected_row) {
+                assert!((derivative - expected).abs() < tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dual_backend()
+    {
+        let (values, jacobian) = evaluate_with(&Hard, X, Backend::Dual);
+        assert_hard_jacobian(values, jacobian, 1.0e-12);
+    }
+
+    #[test]
+    fn test_complex_step_backend()
+    {
+        let (values, jacobian) = evaluate_with(&Hard, X, Backend::complex_step());
+        assert_hard_jacobian(values, jacobian, 1.0e-12);
+    }
+
+    #[test]
+    fn test_finite_difference_backend()
+    {
+        let (values, jacobian) = evaluate_with(&Hard, X, Backend::finite_difference());
+        assert_hard_jacobian(values, jacobian, 1.0e-8);
+    }
+
+    #[test]
+    fn test_backends_agree_on_ln_and_pow()
+    {
+        struct Objective2;
+
+        impl Objective<2, 1> for Objective2
+        {
+            fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 1]
+            {
+                [variables[0].ln() * variables[1].powf(2.5) / variables[0].sqrt()]
+            }
+        }
+
+        let x = [1.5, 0.7];
+        let (_, dual) = evaluate_with(&Objective2, x, Backend::Dual);
+        let (_, complex_step) = evaluate_with(&Objective2, x, Backend::complex_step());
+        assert!(almost_equals(dual[0], complex_step[0]));
+    }
+
+    #[test]
+    fn test_backends_agree_at_negative_inputs()
+    {
+        struct Powers;
+
+        impl Objective<1, 4> for Powers
+        {
+            fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 4]
+            {
+                let x = variables[0];
+                [x.powf(2.0), x.powf(3.0), x.powf(-2.0), x.powf(0.0)]
+            }
+        }
+
+        for x in [-1.0, -2.0, -0.5] {
+            let expected = [2.0 * x, 3.0 * x * x, -2.0 / (x * x * x), 0.0];
+            for backend in [Backend::Dual, Backend::complex_step()] {
+                let (_, jacobian) = evaluate_with(&Powers, [x], backend);
+                for (row, expected) in jacobian.into_iter().zip(expected) {
+                    assert!(almost_eq(row[0], expected), "{:?} at {}", backend, x);
+                }
+            }
+            let (_, jacobian) = evaluate_with(&Powers, [x], Backend::finite_difference());
+            for (row, expected) in jacobian.into_iter().zip(expected) {
+                assert!((row[0] - expected).abs() < 1.0e-6);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_sparse
+{
+    use super::evaluate::*;
+    use super::scalar::*;
+    use super::sparse::*;
+    use super::*;
+
+    // Discretised 1D diffusion residual, every output depends on its neighbours only.
+    struct Diffusion;
+
+    impl Objective<100, 100> for Diffusion
+    {
+        fn call<T: Scalar>(&self, variables: [T; 100]) -> [T; 100]
+        {
+            let mut residual = [T::constant(0.0); 100];
+            for i in 0..100 {
+                let left = if i > 0 {
+                    variables[i - 1]
+                } else {
+                    T::constant(0.0)
+                };
+                let right = if i < 99 {
+                    variables[i + 1]
+                } else {
+                    T::constant(0.0)
+                };
+                residual[i] = left - T::constant(2.0) * variables[i]
+                    + right
+                    + variables[i].sin() * variables[i];
+            }
+            residual
+        }
+    }
+
+    struct Mixed;
+
+    impl Objective<4, 3> for Mixed
+    {
+        fn call<T: Scalar>(&self, variables: [T; 4]) -> [T; 3]
+        {
+            [
+                variables[0] * variables[1],
+                variables[2].exp(),
+                T::constant(3.0),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_detect_sparsity()
+    {
+        let pattern = detect_sparsity(&Mixed, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(pattern.rows, vec![vec![0, 1], vec![2], vec![]]);
+        assert_eq!(pattern.non_zero_count(), 3);
+    }
+
+    #[test]
+    fn test_detect_sparsity_many_inputs()
+    {
+        let pattern = detect_sparsity(&Diffusion, [1.0; 100]);
+        assert_eq!(pattern.rows[0], vec![0, 1]);
+        assert_eq!(pattern.rows[64], vec![63, 64, 65]);
+        assert_eq!(pattern.rows[99], vec![98, 99]);
+    }
+
+    struct Overlapping;
+
+    impl Objective<4, 2> for Overlapping
+    {
+        fn call<T: Scalar>(&self, variables: [T; 4]) -> [T; 2]
+        {
+            let left = variables[3] + variables[0];
+            let right = variables[2] * variables[0];
+            [left * right, (left - variables[3]) / variables[0]]
+        }
+    }
+
+    #[test]
+    fn test_detect_sparsity_merges_sets()
+    {
+        let pattern = detect_sparsity(&Overlapping, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(pattern.rows, vec![vec![0, 2, 3], vec![0, 3]]);
+    }
+
+    #[test]
+    fn test_color_columns()
+    {
+        let pattern = detect_sparsity(&Diffusion, [1.0; 100]);
+        let coloring = color_columns(&pattern);
+        assert_eq!(coloring.color_count, 3);
+
+        let pattern = detect_sparsity(&Mixed, [1.0; 4]);
+        let coloring = color_columns(&pattern);
+        assert_eq!(coloring.color_count, 2);
+        assert_ne!(coloring.colors[0], coloring.colors[1]);
+    }
+
+    #[test]
+    fn test_evaluate_sparse_matches_dense()
+    {
+        let mut x = [0.0; 100];
+        for (i, xi) in x.iter_mut().enumerate() {
+            *xi = 0.1 * i as f64;
+        }
+        let pattern = detect_sparsity(&Diffusion, x);
+        let coloring = color_columns(&pattern);
+        let (values, jacobian) = evaluate_sparse(&Diffusion, x, &pattern, &coloring);
+
+        let (dense_values, dense_jacobian) =
+            backend::evaluate_with(&Diffusion, x, backend::Backend::Dual);
+        assert!(almost_equals(values, dense_values));
+        for (row, dense_row) in jacobian.to_dense().into_iter().zip(dense_jacobian) {
+            for (derivative, expected) in row.into_iter().zip(dense_row) {
+                assert!(almost_eq(derivative, expected));
+            }
+        }
+        assert!(almost_eq(
+            jacobian.get(5, 5),
+            -2.0 + 0.5f64.cos() * 0.5 + 0.5f64.sin()
+        ));
+        assert!(almost_eq(jacobian.get(5, 50), 0.0));
+    }
+
+    #[test]
+    fn test_to_csr()
+    {
+        let x = [1.0, 2.0, 3.0, 4.0];
+        let pattern = detect_sparsity(&Mixed, x);
+        let coloring = color_columns(&pattern);
+        let (_, jacobian) = evaluate_sparse(&Mixed, x, &pattern, &coloring);
+        let csr = jacobian.to_csr();
+        assert_eq!(csr.row_offsets, vec![0, 2, 3, 3]);
+        assert_eq!(csr.column_indices, vec![0, 1, 2]);
+        assert!(almost_equals(
+            [csr.values[0], csr.values[1], csr.values[2]],
+            [2.0, 1.0, f64::exp(3.0)]
+        ));
+    }
+
+    #[test]
+    fn test_get_matches_dense()
+    {
+        let x = [1.0, 2.0, 3.0, 4.0];
+        let pattern = detect_sparsity(&Mixed, x);
+        let coloring = color_columns(&pattern);
+        let (_, jacobian) = evaluate_sparse(&Mixed, x, &pattern, &coloring);
+        for (row, dense_row) in jacobian.to_dense().into_iter().enumerate() {
+            for (column, expected) in dense_row.into_iter().enumerate() {
+                assert_eq!(jacobian.get(row, column), expected);
+            }
+        }
+        assert_eq!(jacobian.get(3, 0), 0.0);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_expression
+{
+    use super::backend::*;
+    use super::evaluate::*;
+    use super::expression::*;
+    use super::fixtures::*;
+    use super::scalar::*;
+    use super::*;
+
+    struct Polynomial;
+
+    impl Objective<2, 1> for Polynomial
+    {
+        fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 1]
+        {
+            let x = variables[0];
+            let y = variables[1];
+            [T::constant(3.0) * x * x + (x - y) * (x + y) / T::constant(1.0)]
+        }
+    }
+
+    #[test]
+    fn test_trace_formula()
+    {
+        let graph = trace(&Polynomial, [1.0, 2.0]);
+        assert_eq!(
+            graph.formula(graph.outputs[0]),
+            "3 * x0 * x0 + (x0 - x1) * (x0 + x1) / 1"
+        );
+        assert_eq!(
+            graph.to_string(),
+            "y0 = 3 * x0 * x0 + (x0 - x1) * (x0 + x1) / 1\n"
+        );
+    }
+
+    #[test]
+    fn test_common_subexpressions_are_shared()
+    {
+        let graph = trace(&Polynomial, [1.0, 2.0]);
+        let inputs = graph
+            .nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Input(_)))
+            .count();
+        assert_eq!(inputs, 2);
+
+        struct Repeated;
+
+        impl Objective<2, 2> for Repeated
+        {
+            fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+            {
+                [
+                    (variables[0] * variables[1]).sin(),
+                    (variables[0] * variables[1]).cos(),
+                ]
+            }
+        }
+
+        struct Reused;
+
+        impl Objective<2, 2> for Reused
+        {
+            fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+            {
+                let product = variables[0] * variables[1];
+                [product.sin(), product.cos()]
+            }
+        }
+
+        let repeated = trace(&Repeated, [1.0, 2.0]);
+        assert_eq!(repeated.nodes.len(), trace(&Reused, [1.0, 2.0]).nodes.len());
+        let operand = |output: usize| match repeated.nodes[repeated.outputs[output]] {
+            Node::Unary(_, operand) => operand,
+            node => panic!("{:?}", node),
+        };
+        assert_eq!(operand(0), operand(1));
+
+        let mut graph = repeated.clone();
+        let count = graph.nodes.len();
+        let product = graph.nodes[operand(0)];
+        assert_eq!(graph.add(product), operand(0));
+        assert_eq!(graph.nodes.len(), count);
+    }
+
+    #[test]
+    fn test_simplify()
+    {
+        let graph = trace(&Polynomial, [1.0, 2.0]).simplify();
+        assert_eq!(
+            graph.formula(graph.outputs[0]),
+            "3 * x0 * x0 + (x0 - x1) * (x0 + x1)"
+        );
+
+        let mut graph = ExpressionGraph::new(1);
+        let x = graph.add(Node::Input(0));
+        let zero = graph.add(Node::Constant(0.0));
+        let two = graph.add(Node::Constant(2.0));
+        let three = graph.add(Node::Constant(3.0));
+        let product = graph.add(Node::Binary(BinaryOperation::Mul, two, three));
+        let sum = graph.add(Node::Binary(BinaryOperation::Add, x, zero));
+        let scaled = graph.add(Node::Binary(BinaryOperation::Mul, product, sum));
+        graph.outputs = vec![scaled];
+        let graph = graph.simplify();
+        assert_eq!(graph.formula(graph.outputs[0]), "6 * x0");
+        assert_eq!(graph.nodes.len(), 3);
+    }
+
+    #[test]
+    fn test_simplify_keeps_zero_products()
+    {
+        // 0 * inf and 0 / 0 are NaN, so 0 * x and 0 / x must not fold to 0.
+        let mut graph = ExpressionGraph::new(1);
+        let x = graph.add(Node::Input(0));
+        let zero = graph.add(Node::Constant(0.0));
+        let product = graph.add(Node::Binary(BinaryOperation::Mul, zero, x));
+        let quotient = graph.add(Node::Binary(BinaryOperation::Div, zero, x));
+        graph.outputs = vec![product, quotient];
+        let graph = graph.simplify();
+        assert_eq!(graph.formula(graph.outputs[0]), "0 * x0");
+        assert_eq!(graph.formula(graph.outputs[1]), "0 / x0");
+        assert_eq!(graph.evaluate(&[2.0]), vec![0.0, 0.0]);
+        assert!(graph.evaluate(&[f64::INFINITY])[0].is_nan());
+        assert!(graph.evaluate(&[0.0])[1].is_nan());
+        assert!(graph
+            .evaluate(&[f64::NAN])
+            .iter()
+            .all(|value| value.is_nan()));
+    }
+
+    #[test]
+    fn test_symbolic_derivative_formula()
+    {
+        let graph = trace(&Polynomial, [1.0, 2.0]);
+        let derivative = graph.derivative(1);
+        assert_eq!(
+            derivative.formula(derivative.outputs[0]),
+            "-(x0 + x1) + x0 - x1"
+        );
+    }
+
+    #[test]
+    fn test_symbolic_derivative_matches_evaluate()
+    {
+        let x = [0.4, 1.3];
+        let graph = trace(&Hard, x);
+        assert!(almost_equals(
+            [
+                graph.evaluate(&x)[0],
+                graph.evaluate(&x)[1],
+                graph.evaluate(&x)[2]
+            ],
+            Hard.call(x)
+        ));
+
+        let (_, jacobian) = evaluate_with(&Hard, x, Backend::Dual);
+        for input in 0..2 {
+            let derivative = graph.derivative(input).evaluate(&x);
+            for (value, row) in derivative.into_iter().zip(jacobian) {
+                assert!(almost_eq(value, row[input]));
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_tape
+{
+    use super::evaluate::*;
+    use super::fixtures::*;
+    use super::scalar::*;
+    use super::tape::*;
+    use super::*;
+
+    #[test]
+    fn test_tape_replay_at_new_points()
+    {
+        let tape = Tape::record(&Hard, [0.4, 1.3]);
+        let mut workspace = tape.workspace();
+        for x in [[0.4, 1.3], [0.2, 0.9], [0.7, 1.1]] {
+            assert_matches_dual(&Hard, x, tape.evaluate(x, &mut workspace));
+            assert!(almost_equals(tape.values(x, &mut workspace), Hard.call(x)));
+        }
+    }
+
+    #[test]
+    fn test_tape_is_simplified()
+    {
+        struct Identity;
+
+        impl Objective<1, 1> for Identity
+        {
+            fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 1]
+            {
+                [variables[0] * T::constant(1.0) + T::constant(0.0)]
+            }
+        }
+
+        let tape = Tape::record(&Identity, [1.0]);
+        assert_eq!(tape.len(), 1);
+    }
+
+    #[test]
+    fn test_tape_shared_between_threads()
+    {
+        let tape = Tape::record(&Hard, [0.4, 1.3]);
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let tape = &tape;
+                scope.spawn(move || {
+                    let mut workspace = tape.workspace();
+                    let x = [0.1 * i as f64 + 0.1, 1.0];
+                    assert_matches_dual(&Hard, x, tape.evaluate(x, &mut workspace));
+                });
+            }
+        });
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_codegen
+{
+    use super::codegen::*;
+    use super::evaluate::*;
+    use super::expression::*;
+    use super::fixtures::*;
+    use super::scalar::*;
+    use super::*;
+
+    struct Constant;
+
+    impl Objective<1, 2> for Constant
+    {
+        fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 2]
+        {
+            [T::constant(-2.0), variables[0] * T::constant(3.0)]
+        }
+    }
+
+    mod generated
+    {
+        include!("../testdata/generated.rs");
+    }
+
+    // The generated code is checked in so that it can be compiled and compared with evaluate.
+    // Run the tests with AUTOCRAB_BLESS=1 to regenerate it after changing the code generator.
+    #[test]
+    fn test_generated_code_is_up_to_date()
+    {
+        let functions = [
+            generate_rust(&trace(&Hard, [0.4, 1.3]), "hard"),
+            generate_rust(&trace(&Constant, [1.0]), "constant"),
+        ];
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/generated.rs");
+        if std::env::var("AUTOCRAB_BLESS").is_ok() {
+            write_rust(path, &functions).unwrap();
+        }
+        let expected = std::fs::read_to_string(path).unwrap();
+        assert_eq!(
+            expected,
+            format!(
+                "// Generated by autocrab, do not edit.\n\n{}\n{}",
+                functions[0], functions[1]
+            )
+        );
+    }
+
+    #[test]
+    fn test_generated_code_matches_evaluate()
+    {
+        for x in [[0.4, 1.3], [0.2, 0.9], [0.7, 1.1]] {
+            assert_matches_dual(&Hard, x, generated::hard(x));
+        }
+
+        let (values, jacobian) = generated::constant([5.0]);
+        assert!(almost_equals(values, [-2.0, 15.0]));
+        assert!(almost_equals([jacobian[0][0], jacobian[1][0]], [0.0, 3.0]));
+    }
+
+    #[test]
+    fn test_common_subexpressions_are_eliminated()
+    {
+        let code = generate_rust(&trace(&Hard, [0.4, 1.3]), "hard");
+        assert_eq!(code.matches(".exp()").count(), 1);
+        assert_eq!(code.matches(".cos()").count(), 2);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_batch
+{
+    use super::backend::*;
+    use super::batch::*;
+    use super::evaluate::*;
+    use super::fixtures::*;
+    use super::scalar::*;
+    use super::*;
+
+    // Covers negation and subtracting a constant, which Hard does not.
+    struct Rational;
+
+    impl Objective<2, 2> for Rational
+    {
+        fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+        {
+            [
+                variables[0].sin() * variables[1].exp() / variables[1].sqrt(),
+                (variables[0] * variables[1]).cos().ln() - variables[0].tan().powf(1.5)
+                    + -variables[1] / (variables[0] - T::constant(2.0)),
+            ]
+        }
+    }
+
+    fn points(count: usize) -> Vec<[f64; 2]>
+    {
+        (0..count)
+            .map(|i| [0.1 + 0.05 * i as f64, 0.5 + 0.03 * i as f64])
+            .collect()
+    }
+
+    fn assert_batch_matches_dual(results: &[BatchResult<2, 2>], points: &[[f64; 2]])
+    {
+        assert_eq!(results.len(), points.len());
+        for (&result, &x) in results.iter().zip(points) {
+            assert_matches_dual(&Rational, x, result);
+        }
+    }
+
+    #[test]
+    fn test_batch_matches_scalar_path()
+    {
+        let points = points(10);
+        assert_batch_matches_dual(&evaluate_batch(&Rational, &points), &points);
+    }
+
+    #[test]
+    fn test_batch_lane_counts()
+    {
+        for count in [0, 1, 7, 8, 9] {
+            let points = points(count);
+            assert_batch_matches_dual(
+                &evaluate_batch_with_lanes::<1, 2, 2>(&Rational, &points),
+                &points,
+            );
+            assert_batch_matches_dual(
+                &evaluate_batch_with_lanes::<8, 2, 2>(&Rational, &points),
+                &points,
+            );
+        }
+    }
+
+    #[test]
+    fn test_powers_at_zero_match_dual()
+    {
+        struct Powers;
+
+        impl Objective<1, 3> for Powers
+        {
+            fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 3]
+            {
+                let x = variables[0];
+                [x.powf(2.0) + x.powf(0.0), x.powf(1.0), x.powf(3.0)]
+            }
+        }
+
+        let points = [[0.0], [-2.0], [0.5]];
+        for (&x, (values, jacobian)) in points.iter().zip(evaluate_batch(&Powers, &points)) {
+            assert_eq!((values, jacobian), evaluate_with(&Powers, x, Backend::Dual));
+        }
+        let (_, jacobian) = evaluate_batch(&Powers, &points)[0];
+        assert_eq!(jacobian, [[0.0], [1.0], [0.0]]);
+    }
+
+    #[test]
+    fn test_lanes_arithmetic()
+    {
+        let x = Lanes::new([1.0, 2.0], [1.0, 1.0]);
+        let y = x * x / (x + Lanes::constant(1.0));
+        assert!(almost_equals(y.values, [0.5, 4.0 / 3.0]));
+        assert!(almost_equals(y.derivatives, [0.75, 8.0 / 9.0]));
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod test_parallel
+{
+    use super::backend::*;
+    use super::batch::*;
+    use super::evaluate::*;
+    use super::scalar::*;
+    use super::variable::*;
+
+    const SIZE: usize = 24;
+
+    struct Wide;
+
+    impl Objective<SIZE, 2> for Wide
+    {
+        fn call<T: Scalar>(&self, variables: [T; SIZE]) -> [T; 2]
+        {
+            let mut sum = T::constant(0.0);
+            let mut product = T::constant(1.0);
+            for (i, &variable) in variables.iter().enumerate() {
+                sum = sum + (variable * T::constant(i as f64)).sin();
+                product = product * (variable.exp() + T::constant(1.0)).ln();
+            }
+            [sum, product]
+        }
+    }
+
+    fn wide(variables: [Variable; SIZE]) -> [Variable; 2]
+    {
+        Wide.call(variables)
+    }
+
+    fn point(offset: f64) -> [f64; SIZE]
+    {
+        std::array::from_fn(|i| offset + 0.1 * i as f64)
+    }
+
+    #[test]
+    fn test_parallel_columns_are_bit_identical()
+    {
+        const { assert!(SIZE >= PARALLEL_COLUMN_THRESHOLD) };
+        let x = point(0.3);
+        assert_eq!(evaluate(wide, x), evaluate_with(&Wide, x, Backend::Dual));
+        assert_eq!(evaluate(wide, x), evaluate(wide, x));
+    }
+
+    #[test]
+    fn test_par_evaluate_batch_is_bit_identical()
+    {
+        let points: Vec<_> = (0..1001).map(|i| point(0.001 * i as f64)).collect();
+        let serial = evaluate_batch(&Wide, &points);
+        assert_eq!(par_evaluate_batch(&Wide, &points), serial);
+        assert_eq!(
+            par_evaluate_batch_with_lanes::<8, SIZE, 2>(&Wide, &points),
+            evaluate_batch_with_lanes::<8, SIZE, 2>(&Wide, &points)
+        );
+    }
+}
+
+// With the libm feature the math module uses libm even on the host, so the results can be
+// compared against the standard library.
+#[cfg(all(test, feature = "libm"))]
+mod test_math
+{
+    use super::evaluate::*;
+    use super::functions::*;
+    use super::variable::*;
+    use super::*;
+
+    fn points() -> impl Iterator<Item = f64>
+    {
+        (1..200).map(|i| 0.037 * i as f64)
+    }
+
+    fn assert_close(value: f64, expected: f64)
+    {
+        assert!(
+            (value - expected).abs() <= 4.0 * f64::EPSILON * expected.abs().max(1.0),
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_libm_matches_std()
+    {
+        for x in points() {
+            assert_close(math::sqrt(x), x.sqrt());
+            assert_close(math::sin(x), x.sin());
+            assert_close(math::cos(x), x.cos());
+            assert_close(math::tan(x), x.tan());
+            assert_close(math::ln(x), x.ln());
+            assert_close(math::exp(x), x.exp());
+            assert_close(math::sinh(x), x.sinh());
+            assert_close(math::cosh(x), x.cosh());
+            assert_close(math::powf(x, 2.5), x.powf(2.5));
+            assert_close(math::atan2(x, 1.5), x.atan2(1.5));
+            assert_close(math::hypot(x, 1.5), x.hypot(1.5));
+        }
+    }
+
+    fn objective(variables: [Variable; 2]) -> [Variable; 1]
+    {
+        [
+            sin(variables[0]) * exp(variables[1]) + ln(variables[0]) / sqrt(variables[1])
+                - pow(tan(variables[0]), 1.5)
+                + cos(variables[1]),
+        ]
+    }
+
+    #[test]
+    fn test_libm_derivatives_match_std()
+    {
+        for x in points().filter(|x| x.tan() > 0.0) {
+            let y = 0.5 + x;
+            let (values, jacobian) = evaluate(objective, [x, y]);
+            let sec = 1.0 / x.cos();
+            assert_close(
+                values[0],
+                x.sin() * y.exp() + x.ln() / y.sqrt() - x.tan().powf(1.5) + y.cos(),
+            );
+            assert_close(
+                jacobian[0][0],
+                x.cos() * y.exp() + 1.0 / (x * y.sqrt()) - 1.5 * x.tan().sqrt() * sec * sec,
+            );
+            assert_close(
+                jacobian[0][1],
+                x.sin() * y.exp() - 0.5 * x.ln() / (y * y.sqrt()) - y.sin(),
+            );
+        }
+    }
+
+    // Every evaluation path calls the same math functions, so the values agree exactly whichever
+    // backend provides them.
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_paths_agree_exactly()
+    {
+        use super::backend::*;
+        use super::batch::*;
+        use super::expression::*;
+        use super::scalar::*;
+        use super::tape::*;
+
+        struct Transcendental;
+
+        impl Objective<2, 1> for Transcendental
+        {
+            fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 1]
+            {
+                let [x, y] = variables;
+                [x.sin() * y.exp() + x.ln() / y.sqrt() - x.tan().powf(1.5) + y.cos()]
+            }
+        }
+
+        for x in points().filter(|x| x.tan() > 0.0) {
+            let point = [x, 0.5 + x];
+            let (values, _) = evaluate_with(&Transcendental, point, Backend::Dual);
+            assert_eq!(evaluate_batch(&Transcendental, &[point])[0].0, values);
+            assert_eq!(trace(&Transcendental, point).evaluate(&point), values);
+            let tape = Tape::record(&Transcendental, point);
+            assert_eq!(tape.values(point, &mut tape.workspace()), values);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "serde"))]
+mod test_serde
+{
+    use super::complex::*;
+    use super::expression::*;
+    use super::fixtures::*;
+    use super::functions::*;
+    use super::report::*;
+    use super::sparse::*;
+    use super::variable::*;
+
+    fn objective_function(variables: [Variable; 2]) -> [Variable; 2]
+    {
+        [
+            variables[0] * variables[1],
+            sin(variables[0]) + variables[1],
+        ]
+    }
+
+    #[test]
+    fn test_variable_round_trip()
+    {
+        let variable = Variable::with_derivative(1.5, -0.25);
+        let json = serde_json::to_string(&variable).unwrap();
+        assert_eq!(json, r#"{"value":1.5,"derivative":-0.25}"#);
+        let loaded: Variable = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.value, variable.value);
+        assert_eq!(loaded.derivative, variable.derivative);
+
+        let complex = Complex::new(1.0, -2.0);
+        let loaded: Complex =
+            serde_json::from_str(&serde_json::to_string(&complex).unwrap()).unwrap();
+        assert_eq!(loaded, complex);
+    }
+
+    #[test]
+    fn test_report_layout()
+    {
+        let report = EvaluationReport::evaluate(objective_function, [2.0, 3.0])
+            .with_metadata("objective", "example");
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["input"], serde_json::json!([2.0, 3.0]));
+        assert_eq!(json["values"][0], 6.0);
+        assert_eq!(json["jacobian"][0], serde_json::json!([3.0, 2.0]));
+        assert_eq!(json["jacobian"][1][1], 1.0);
+        assert_eq!(json["metadata"]["objective"], "example");
+
+        let loaded: EvaluationReport = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, report);
+        assert_eq!(
+            loaded.to_arrays::<2, 2>(),
+            Some(super::evaluate::evaluate(objective_function, [2.0, 3.0]))
+        );
+        assert_eq!(loaded.to_arrays::<3, 2>(), None);
+    }
+
+    #[test]
+    fn test_report_without_metadata()
+    {
+        let json = r#"{"input": [1.0], "values": [2.0], "jacobian": [[3.0]]}"#;
+        let report: EvaluationReport = serde_json::from_str(json).unwrap();
+        assert!(report.metadata.is_empty());
+        assert_eq!(report.to_arrays::<1, 1>(), Some(([2.0], [[3.0]])));
+    }
+
+    #[test]
+    fn test_report_non_finite_round_trip()
+    {
+        let report = EvaluationReport::new(
+            [0.0, 1.0],
+            [f64::NAN, 1.0],
+            [[f64::INFINITY, f64::NEG_INFINITY], [0.0, 2.0]],
+        );
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(
+            json,
+            r#"{"input":[0.0,1.0],"values":["NaN",1.0],"jacobian":[["inf","-inf"],[0.0,2.0]],"metadata":{}}"#
+        );
+
+        let loaded: EvaluationReport = serde_json::from_str(&json).unwrap();
+        assert!(loaded.values[0].is_nan());
+        assert_eq!(loaded.values[1], 1.0);
+        assert_eq!(loaded.jacobian, report.jacobian);
+        assert_eq!(loaded.input, report.input);
+
+        let json = r#"{"input": [1], "values": ["infinity"], "jacobian": [[0]]}"#;
+        assert!(serde_json::from_str::<EvaluationReport>(json).is_err());
+    }
+
+    #[test]
+    fn test_report_binary_round_trip()
+    {
+        let report = EvaluationReport::new([0.0], [f64::NEG_INFINITY], [[f64::NAN]])
+            .with_metadata("format", "bincode");
+        let bytes = bincode::serialize(&report).unwrap();
+        let loaded: EvaluationReport = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(loaded.values, report.values);
+        assert!(loaded.jacobian[0][0].is_nan());
+        assert_eq!(loaded.metadata, report.metadata);
+    }
+
+    #[test]
+    fn test_sparse_and_graph_round_trip()
+    {
+        let x = [0.5, 1.5, 2.5];
+        let pattern = detect_sparsity(&Chain, x);
+        let coloring = color_columns(&pattern);
+        let (_, jacobian) = evaluate_sparse(&Chain, x, &pattern, &coloring);
+        let loaded: SparseJacobian =
+            serde_json::from_str(&serde_json::to_string(&jacobian).unwrap()).unwrap();
+        assert_eq!(loaded, jacobian);
+
+        let graph = trace(&Chain, x);
+        let mut loaded: ExpressionGraph =
+            serde_json::from_str(&serde_json::to_string(&graph).unwrap()).unwrap();
+        assert_eq!(loaded.nodes, graph.nodes);
+        assert_eq!(loaded.evaluate(&x), graph.evaluate(&x));
+        let node_count = loaded.nodes.len();
+        loaded.add(graph.nodes[node_count - 1]);
+        assert_eq!(loaded.nodes.len(), node_count);
+    }
+
+    #[test]
+    fn test_invalid_graph_is_rejected()
+    {
+        let load = |json: &str| serde_json::from_str::<ExpressionGraph>(json);
+
+        let valid =
+            r#"{"nodes": [{"Input": 0}, {"Unary": ["Sin", 0]}], "outputs": [1], "input_count": 1}"#;
+        assert_eq!(load(valid).unwrap().evaluate(&[0.0]), [0.0]);
+
+        let forward = r#"{"nodes": [{"Input": 0}, {"Binary": ["Mul", 0, 7]}], "outputs": [1], "input_count": 1}"#;
+        let error = load(forward).unwrap_err().to_string();
+        assert!(error.contains("node 1 uses node 7"), "{}", error);
+
+        let own =
+            r#"{"nodes": [{"Input": 0}, {"Powf": [1, 2.0]}], "outputs": [1], "input_count": 1}"#;
+        assert!(load(own).is_err());
+
+        let input = r#"{"nodes": [{"Input": 3}], "outputs": [0], "input_count": 1}"#;
+        assert!(load(input).unwrap_err().to_string().contains("input 3"));
+
+        let output = r#"{"nodes": [{"Input": 0}], "outputs": [4], "input_count": 1}"#;
+        assert!(load(output)
+            .unwrap_err()
+            .to_string()
+            .contains("output node 4"));
+    }
+}
+
+#[cfg(test)]
+mod test_checked
+{
+    use super::backend::*;
+    use super::checked::*;
+    use super::evaluate::*;
+    use super::scalar::*;
+
+    struct Logarithm;
+
+    impl Objective<2, 1> for Logarithm
+    {
+        fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 1]
+        {
+            [(variables[0] - variables[1]).ln() * variables[1].exp()]
+        }
+    }
+
+    struct Ratio;
+
+    impl Objective<2, 1> for Ratio
+    {
+        fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 1]
+        {
+            [variables[0] / variables[1]]
+        }
+    }
+
+    struct Root;
+
+    impl Objective<1, 2> for Root
+    {
+        fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 2]
+        {
+            [
+                variables[0].sqrt(),
+                variables[0].powf(0.5).exp().exp().exp(),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_try_evaluate_valid()
+    {
+        let x = [2.0, 0.5];
+        assert_eq!(
+            try_evaluate(&Logarithm, x),
+            Ok(evaluate_with(&Logarithm, x, Backend::Dual))
+        );
+    }
+
+    #[test]
+    fn test_try_evaluate_logarithm_of_negative()
+    {
+        let error = try_evaluate(&Logarithm, [0.5, 2.0]).unwrap_err();
+        assert_eq!(error.primitive, Primitive::Ln);
+        assert_eq!(error.argument, -1.5);
+        assert_eq!(error.kind, DomainErrorKind::OutOfDomain);
+        assert_eq!(error.to_string(), "Ln is undefined at -1.5");
+    }
+
+    #[test]
+    fn test_try_evaluate_division_by_zero()
+    {
+        let error = try_evaluate(&Ratio, [1.0, 0.0]).unwrap_err();
+        assert_eq!(error.primitive, Primitive::Div);
+        assert_eq!(error.argument, 0.0);
+    }
+
+    #[test]
+    fn test_try_evaluate_root_at_zero()
+    {
+        let error = try_evaluate(&Root, [0.0]).unwrap_err();
+        assert_eq!(error.primitive, Primitive::Sqrt);
+        assert_eq!(error.kind, DomainErrorKind::NonFinite);
+
+        let error = try_evaluate(&Root, [-1.0]).unwrap_err();
+        assert_eq!(error.primitive, Primitive::Sqrt);
+        assert_eq!(error.kind, DomainErrorKind::OutOfDomain);
+    }
+
+    #[test]
+    fn test_tracking_records_first_non_finite()
+    {
+        let (values, _, error) = evaluate_tracked(&Root, [100.0]);
+        assert_eq!(values[0], 10.0);
+        assert!(values[1].is_infinite());
+        let error = error.unwrap();
+        assert_eq!(error.primitive, Primitive::Exp);
+        assert_eq!(error.kind, DomainErrorKind::NonFinite);
+        assert_eq!(error.argument, 10.0f64.exp());
+
+        let (values, jacobian, error) = evaluate_tracked(&Logarithm, [2.0, 0.5]);
+        assert_eq!(
+            (values, jacobian),
+            evaluate_with(&Logarithm, [2.0, 0.5], Backend::Dual)
+        );
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    fn test_error_propagates_first()
+    {
+        let x = Checked::constant(-1.0);
+        let y = x.ln() + x.sqrt();
+        assert_eq!(y.error.unwrap().primitive, Primitive::Ln);
+        let y = x.sqrt() * x.ln();
+        assert_eq!(y.error.unwrap().primitive, Primitive::Sqrt);
+        assert_eq!(Checked::constant(-8.0).powf(3.0).error, None);
+        assert_eq!(
+            Checked::constant(-8.0).powf(1.0 / 3.0).error.unwrap().kind,
+            DomainErrorKind::OutOfDomain
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_singular_points
+{
+    use super::evaluate::*;
+    use super::functions::*;
+    use super::linalg::*;
+    use super::math;
+    use super::variable::*;
+
+    #[test]
+    fn test_sqrt_at_zero()
+    {
+        let y = sqrt(Variable::with_derivative(0.0, 0.0));
+        assert_eq!((y.value, y.derivative), (0.0, 0.0));
+        let y = sqrt(Variable::seeded(0.0));
+        assert_eq!(y.derivative, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_pow_at_zero()
+    {
+        let constant = Variable::with_derivative(0.0, 0.0);
+        assert_eq!(pow(constant, 0.5).derivative, 0.0);
+        assert_eq!(pow(constant, -1.0).derivative, 0.0);
+
+        let y = pow(Variable::seeded(0.0), 0.0);
+        assert_eq!((y.value, y.derivative), (1.0, 0.0));
+        let y = pow(Variable::seeded(0.0), 1.0);
+        assert_eq!((y.value, y.derivative), (0.0, 1.0));
+        let y = pow(Variable::seeded(0.0), 2.0);
+        assert_eq!((y.value, y.derivative), (0.0, 0.0));
+        let y = pow(Variable::seeded(0.0), 2.5);
+        assert_eq!((y.value, y.derivative), (0.0, 0.0));
+        let y = pow(Variable::seeded(0.0), 0.5);
+        assert_eq!(y.derivative, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_pow_integer_exponents()
+    {
+        let y = pow(Variable::seeded(-2.0), 3.0);
+        assert_eq!((y.value, y.derivative), (-8.0, 12.0));
+        let y = pow(Variable::seeded(-2.0), 2.0);
+        assert_eq!((y.value, y.derivative), (4.0, -4.0));
+        let y = pow(Variable::seeded(2.0), -2.0);
+        assert_eq!((y.value, y.derivative), (0.25, -0.25));
+        for exponent in -5..=5 {
+            assert_eq!(math::powi(1.5, exponent), 1.5f64.powi(exponent));
+        }
+    }
+
+    #[test]
+    fn test_abs_at_zero()
+    {
+        let y = abs(Variable::seeded(0.0));
+        assert_eq!((y.value, y.derivative), (0.0, 0.0));
+        let y = abs(Variable::seeded(-3.0));
+        assert_eq!((y.value, y.derivative), (3.0, -1.0));
+        let y = abs(Variable::seeded(3.0));
+        assert_eq!((y.value, y.derivative), (3.0, 1.0));
+    }
+
+    fn norm(variables: [Variable; 3]) -> [Variable; 1]
+    {
+        [Vector(variables).norm()]
+    }
+
+    #[test]
+    fn test_norm_of_zero_vector()
+    {
+        let (values, jacobian) = evaluate(norm, [0.0; 3]);
+        assert_eq!(values, [0.0]);
+        assert_eq!(jacobian, [[0.0; 3]]);
+
+        let (_, jacobian) = evaluate(norm, [3.0, 0.0, 4.0]);
+        assert!(super::almost_equals(jacobian[0], [0.6, 0.0, 0.8]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_lanes_norm_of_zero_vector()
+    {
+        use super::batch::*;
+        use super::scalar::*;
+
+        let x = Lanes::<2>::new([0.0, 3.0], [1.0, 1.0]);
+        let y = Lanes::constant(0.0);
+        let norm = Vector([x, y]).norm();
+        assert_eq!(norm.values, [0.0, 3.0]);
+        assert_eq!(norm.derivatives, [0.0, 1.0]);
+    }
+}
+
+#[cfg(test)]
+mod test_display
+{
+    use super::evaluate::*;
+    use super::functions::*;
+    use super::table::*;
+    use super::variable::*;
+
+    #[test]
+    fn test_variable_display()
+    {
+        let variable = Variable::with_derivative(1.5, 0.25);
+        assert_eq!(variable.to_string(), "1.5 + 0.25·ε");
+        assert_eq!(format!("{:.3}", variable), "1.500 + 0.250·ε");
+        let variable = Variable::with_derivative(-2.0, -1.0 / 3.0);
+        assert_eq!(format!("{:.2}", variable), "-2.00 - 0.33·ε");
+    }
+
+    fn objective_function(variables: [Variable; 2]) -> [Variable; 2]
+    {
+        [
+            variables[0] * variables[1],
+            sin(variables[0]) + variables[1],
+        ]
+    }
+
+    #[test]
+    fn test_jacobian_table()
+    {
+        let jacobian = [[1.0, -20.5], [300.0, 0.0]];
+        assert_eq!(
+            JacobianTable::new(&jacobian).to_string(),
+            "     x0     x1\n\
+             y0    1  -20.5\n\
+             y1  300      0\n"
+        );
+    }
+
+    #[test]
+    fn test_evaluation_table()
+    {
+        let result = evaluate(objective_function, [2.0, 3.0]);
+        let table = JacobianTable::evaluation(&result)
+            .with_input_labels(["temperature", "p"])
+            .with_output_labels(["flow", "heat loss"]);
+        assert_eq!(
+            format!("{:.3}", table),
+            "           value  temperature      p\n\
+             flow       6.000        3.000  2.000\n\
+             heat loss  3.909       -0.416  1.000\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_named
+{
+    use super::functions::*;
+    use super::named::*;
+    use super::variable::*;
+
+    fn pipe(variables: [Variable; 3]) -> [Variable; 2]
+    {
+        let temperature = variables[0];
+        let pressure = variables[1];
+        let diameter = variables[2];
+        [
+            pressure * square(diameter) / temperature,
+            temperature * sqrt(pressure),
+        ]
+    }
+
+    fn model() -> NamedObjective<'static, 3, 2>
+    {
+        NamedObjective::new(
+            pipe,
+            ["temperature", "pressure", "diameter"],
+            ["flow", "heat"],
+        )
+    }
+
+    #[test]
+    fn test_index_by_name()
+    {
+        let result = model().evaluate([2.0, 4.0, 3.0]);
+        assert_eq!(result.value("flow"), Some(18.0));
+        assert_eq!(result["flow"]["pressure"], 9.0 / 2.0);
+        assert_eq!(result["flow"]["diameter"], 4.0 * 2.0 * 3.0 / 2.0);
+        assert_eq!(result["heat"]["temperature"], 2.0);
+        assert_eq!(result["heat"]["pressure"], 2.0 / (2.0 * 2.0));
+        assert_eq!(result.get("heat", "diameter"), Some(0.0));
+        assert_eq!(result.get("heat", "volume"), None);
+        assert_eq!(result.get("cost", "pressure"), None);
+        assert_eq!(result.jacobian[0][1], result["flow"]["pressure"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown output \"cost\"")]
+    fn test_index_unknown_output()
+    {
+        let result = model().evaluate([2.0, 4.0, 3.0]);
+        let _ = result["cost"];
+    }
+
+    #[test]
+    fn test_evaluate_named()
+    {
+        let model = model();
+        let result = model
+            .evaluate_named(&[("diameter", 3.0), ("temperature", 2.0), ("pressure", 4.0)])
+            .unwrap();
+        assert_eq!(result, model.evaluate([2.0, 4.0, 3.0]));
+
+        assert_eq!(
+            model.point(&[("temperature", 2.0), ("pressure", 4.0)]),
+            Err(NameError::Missing("diameter"))
+        );
+        assert_eq!(
+            model.point(&[("volume", 2.0)]),
+            Err(NameError::Unknown("volume"))
+        );
+        assert_eq!(
+            model.point(&[("pressure", 2.0), ("pressure", 4.0)]),
+            Err(NameError::Duplicate("pressure"))
+        );
+    }
+
+    #[test]
+    fn test_named_table()
+    {
+        let result = model().evaluate([2.0, 4.0, 3.0]);
+        assert_eq!(
+            format!("{:.1}", result.table()),
+            "      value  temperature  pressure  diameter\n\
+             flow   18.0         -9.0       4.5      12.0\n\
+             heat    4.0          2.0       0.5       0.0\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "used twice")]
+    fn test_duplicate_names()
+    {
+        NamedObjective::new(pipe, ["a", "b", "a"], ["flow", "heat"]);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_structs
+{
+    use super::evaluate::*;
+    use super::functions::*;
+    use super::structs::*;
+    use super::variable::*;
+
+    fn objective_function(variables: [Variable; 3]) -> [Variable; 2]
+    {
+        [
+            variables[0] * variables[1],
+            sin(variables[2]) / variables[0],
+        ]
+    }
+
+    fn nested(points: [[Variable; 2]; 2]) -> Variable
+    {
+        points[0][0] * points[1][1] - points[0][1] * points[1][0]
+    }
+
+    #[test]
+    fn test_arrays_match_evaluate()
+    {
+        let x = [2.0, 3.0, 0.5];
+        let (values, jacobian) = evaluate_struct(objective_function, &x);
+        let (expected_values, expected_jacobian) = evaluate(objective_function, x);
+        assert_eq!(values, expected_values);
+        assert_eq!(jacobian.rows, expected_jacobian);
+        assert_eq!(jacobian.output_names, ["[0]", "[1]"]);
+        assert_eq!(jacobian["[1]"][2], expected_jacobian[1][2]);
+    }
+
+    #[test]
+    fn test_nested_arrays()
+    {
+        let points = [[1.0, 2.0], [3.0, 4.0]];
+        assert_eq!(<[[f64; 2]; 2]>::SIZE, 4);
+        assert_eq!(points.to_array::<4>(), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(<[[f64; 2]; 2]>::from_array([1.0, 2.0, 3.0, 4.0]), points);
+
+        let (value, jacobian) = evaluate_struct(nested, &points);
+        assert_eq!(value, -2.0);
+        assert_eq!(jacobian.output_names, [""]);
+        assert_eq!(jacobian.rows, [[[4.0, -3.0], [-2.0, 1.0]]]);
+    }
+}
+
+#[cfg(test)]
+mod test_uncertainty
+{
+    use super::functions::*;
+    use super::linalg::*;
+    use super::uncertainty::*;
+    use super::variable::*;
+
+    fn relative_error(value: f64, expected: f64) -> f64
+    {
+        ((value - expected) / expected).abs()
+    }
+
+    fn linear(variables: [Variable; 2]) -> [Variable; 2]
+    {
+        [
+            2.0 * variables[0] - variables[1],
+            variables[0] + 3.0 * variables[1] + 1.0,
+        ]
+    }
+
+    fn product(variables: [Variable; 2]) -> [Variable; 1]
+    {
+        [variables[0] * variables[1]]
+    }
+
+    fn sine(variables: [Variable; 1]) -> [Variable; 1]
+    {
+        [sin(variables[0])]
+    }
+
+    #[test]
+    fn test_propagate_linear()
+    {
+        let measurement = Measurement::new([1.0, 2.0], [[0.04, 0.01], [0.01, 0.09]]);
+        let output = measurement.propagate(linear);
+        assert_eq!(output.mean.0, [0.0, 8.0]);
+
+        let jacobian = Matrix([[2.0, -1.0], [1.0, 3.0]]);
+        let expected = jacobian * measurement.covariance * jacobian.transpose();
+        for (row, expected_row) in output.covariance.0.iter().zip(expected.0) {
+            assert!(super::almost_equals(*row, expected_row));
+        }
+    }
+
+    #[test]
+    fn test_propagate_product()
+    {
+        let measurement = Measurement::independent([3.0, 4.0], [0.1, 0.2]);
+        assert_eq!(measurement.standard_deviations(), [0.1, 0.2]);
+        let output = measurement.propagate(product);
+        assert_eq!(output.mean.0, [12.0]);
+        // var(xy) = y² var(x) + x² var(y)
+        assert!(super::almost_eq(
+            output.covariance[(0, 0)],
+            16.0 * 0.01 + 9.0 * 0.04
+        ));
+    }
+
+    #[test]
+    fn test_monte_carlo_matches_linearisation()
+    {
+        let measurement = Measurement::new([1.0, 2.0], [[0.04, 0.01], [0.01, 0.09]]);
+        let exact = measurement.propagate(linear);
+        let sampled = measurement.monte_carlo(linear, 20_000, 7).unwrap();
+        for i in 0..2 {
+            assert!((sampled.mean[i] - exact.mean[i]).abs() < 0.01);
+            for j in 0..2 {
+                assert!(
+                    relative_error(sampled.covariance[(i, j)], exact.covariance[(i, j)]) < 0.05
+                );
+            }
+        }
+        assert!(relative_error(sampled.correlation(0, 1), exact.correlation(0, 1)) < 0.05);
+
+        // Reproducible for a seed.
+        assert_eq!(
+            measurement.monte_carlo(linear, 1000, 7),
+            measurement.monte_carlo(linear, 1000, 7)
+        );
+        assert_ne!(
+            measurement.monte_carlo(linear, 1000, 7),
+            measurement.monte_carlo(linear, 1000, 8)
+        );
+    }
+
+    #[test]
+    fn test_monte_carlo_shows_nonlinearity()
+    {
+        // sin is flat at pi/2, the linearisation predicts no spread at all.
+        let measurement = Measurement::independent([std::f64::consts::FRAC_PI_2], [0.3]);
+        let linearised = measurement.propagate(sine);
+        assert!(linearised.covariance[(0, 0)].abs() < 1.0e-12);
+        let sampled = measurement.monte_carlo(sine, 20_000, 1).unwrap();
+        // E[sin(x)] = exp(-σ²/2) for x ~ N(pi/2, σ²).
+        assert!((sampled.mean[0] - (-0.045f64).exp()).abs() < 0.01);
+        assert!(sampled.covariance[(0, 0)] > 1.0e-3);
+    }
+
+    #[test]
+    fn test_exactly_known_input()
+    {
+        let measurement = Measurement::independent([3.0, 4.0], [0.0, 0.2]);
+        let sampled = measurement.monte_carlo(product, 20_000, 3).unwrap();
+        let exact = measurement.propagate(product);
+        assert!(relative_error(sampled.covariance[(0, 0)], exact.covariance[(0, 0)]) < 0.05);
+
+        let invalid = Measurement::new([0.0, 0.0], [[1.0, 2.0], [2.0, 1.0]]);
+        assert_eq!(invalid.monte_carlo(product, 100, 3), None);
+    }
+
+    #[test]
+    fn test_cholesky()
+    {
+        let matrix = Matrix([[4.0, 2.0, -2.0], [2.0, 10.0, 2.0], [-2.0, 2.0, 6.0]]);
+        let lower = matrix.cholesky().unwrap();
+        assert_eq!(lower[(0, 1)], 0.0);
+        let product = lower * lower.transpose();
+        for (row, expected_row) in product.0.iter().zip(matrix.0) {
+            assert!(super::almost_equals(*row, expected_row));
+        }
+
+        // Semidefinite with an exactly known first input.
+        let singular = Matrix([[0.0, 0.0], [0.0, 4.0]]);
+        assert_eq!(singular.cholesky(), Some(Matrix([[0.0, 0.0], [0.0, 2.0]])));
+
+        // Zero pivot with a nonzero entry below it, the eigenvalues are 1 and -1.
+        assert_eq!(Matrix([[0.0, 1.0], [1.0, 0.0]]).cholesky(), None);
+        assert_eq!(Matrix([[1.0, 0.5], [0.0, 1.0]]).cholesky(), None);
+
+        let measurement = Measurement::new([0.0, 0.0], [[0.0, 1.0], [1.0, 0.0]]);
+        assert_eq!(measurement.monte_carlo(linear, 10, 3), None);
+    }
+}
+
+#[cfg(test)]
+mod test_sensitivity
+{
+    use super::functions::*;
+    use super::sensitivity::*;
+    use super::variable::*;
+
+    // Power law with known elasticities 1 and 2.
+    fn power_law(variables: [Variable; 2]) -> [Variable; 2]
+    {
+        [
+            variables[0] * square(variables[1]),
+            variables[0] - variables[1],
+        ]
+    }
+
+    #[test]
+    fn test_elasticities()
+    {
+        let sensitivity = Sensitivity::evaluate(power_law, [3.0, 2.0]);
+        assert!(super::almost_eq(sensitivity.elasticity(0, 0).unwrap(), 1.0));
+        assert!(super::almost_eq(sensitivity.elasticity(0, 1).unwrap(), 2.0));
+        assert!(super::almost_eq(sensitivity.elasticity(1, 0).unwrap(), 3.0));
+        assert!(super::almost_eq(
+            sensitivity.elasticity(1, 1).unwrap(),
+            -2.0
+        ));
+        assert_eq!(
+            sensitivity.elasticities()[0][1],
+            sensitivity.elasticity(0, 1)
+        );
+        assert!(super::almost_eq(
+            sensitivity.condition_number(0).unwrap(),
+            3.0
+        ));
+        assert!(super::almost_eq(
+            sensitivity.condition_number(1).unwrap(),
+            5.0
+        ));
+        assert_eq!(sensitivity.ranking(0), [1, 0]);
+        assert_eq!(sensitivity.normalised(0, 1), 12.0 * 2.0);
+    }
+
+    #[test]
+    fn test_zero_values()
+    {
+        // x0 = 0 makes the first output zero, x0 = x1 the second.
+        let sensitivity = Sensitivity::evaluate(power_law, [0.0, 0.0]);
+        assert_eq!(sensitivity.elasticity(0, 0), None);
+        assert_eq!(sensitivity.condition_number(0), Some(0.0));
+
+        let sensitivity = Sensitivity::evaluate(power_law, [0.0, 2.0]);
+        assert_eq!(sensitivity.elasticity(1, 0), Some(0.0));
+        assert!(super::almost_eq(sensitivity.elasticity(1, 1).unwrap(), 1.0));
+        assert_eq!(sensitivity.normalised(1, 0), 0.0);
+        assert_eq!(sensitivity.elasticity(0, 1), None);
+        assert_eq!(sensitivity.condition_number(0), Some(0.0));
+        let moved = Sensitivity::evaluate(power_law, [2.0, 2.0]);
+        assert_eq!(moved.condition_number(1), None);
+
+        // A scale gives the input at zero a sensitivity again.
+        let sensitivity = sensitivity.with_scales([0.5, 0.1]);
+        assert_eq!(sensitivity.normalised(0, 0), 4.0 * 0.5);
+        assert_eq!(sensitivity.ranking(0), [0, 1]);
+        for row in sensitivity.elasticities() {
+            for elasticity in row.into_iter().flatten() {
+                assert!(elasticity.is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn test_table()
+    {
+        let sensitivity = Sensitivity::evaluate(power_law, [3.0, 2.0])
+            .with_input_labels(["load", "length"])
+            .with_output_labels(["moment", "slack"]);
+        let expected = "\
+output  input   rank  derivative  elasticity  normalised  condition
+moment  length     1      12.000       2.000      24.000      3.000
+moment  load       2       4.000       1.000      12.000      3.000
+slack   load       1       1.000       3.000       3.000      5.000
+slack   length     2      -1.000      -2.000      -2.000      5.000
+";
+        assert_eq!(
+            format!("{:.3}", sensitivity.table(Order::Influence)),
+            expected
+        );
+
+        let by_input = format!("{:.1}", sensitivity.table(Order::Input));
+        assert!(by_input
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("moment  load  "));
+
+        let undefined = Sensitivity::evaluate(power_law, [0.0, 2.0]);
+        let table = format!("{:.1}", undefined.table(Order::Input));
+        assert!(table.lines().nth(1).unwrap().contains(" -"));
+    }
+
+    #[test]
+    fn test_csv()
+    {
+        // The second output is zero, its elasticities and condition number are undefined.
+        let sensitivity = Sensitivity::evaluate(power_law, [2.0, 2.0]);
+        let expected = "\
+output,input,rank,derivative,elasticity,normalised,condition
+y0,x1,1,8,2,16,3
+y0,x0,2,4,1,8,3
+y1,x0,1,1,,2,
+y1,x1,2,-1,,-2,
+";
+        assert_eq!(format!("{}", sensitivity.csv(Order::Influence)), expected);
+    }
+
+    #[test]
+    fn test_csv_quotes_labels()
+    {
+        let sensitivity = Sensitivity::evaluate(power_law, [2.0, 2.0])
+            .with_input_labels(["load, axial", "span \"L\""])
+            .with_output_labels(["moment", "shear\nforce"]);
+        let expected = "\
+output,input,rank,derivative,elasticity,normalised,condition
+moment,\"load, axial\",2,4,1,8,3
+moment,\"span \"\"L\"\"\",1,8,2,16,3
+\"shear\nforce\",\"load, axial\",1,1,,2,
+\"shear\nforce\",\"span \"\"L\"\"\",2,-1,,-2,
+";
+        assert_eq!(format!("{}", sensitivity.csv(Order::Input)), expected);
+    }
+}
+
+#[cfg(test)]
+mod test_interval
+{
+    use super::backend::*;
+    use super::evaluate::*;
+    use super::interval::*;
+    use super::scalar::*;
+    use super::variable::*;
+
+    struct Cubic;
+
+    impl Objective<1, 1> for Cubic
+    {
+        fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 1]
+        {
+            let x = variables[0];
+            [x * x * x - T::constant(2.0) * x + (x * T::constant(0.5)).sin().exp()]
+        }
+    }
+
+    #[test]
+    fn test_outward_rounding()
+    {
+        let sum = Interval::point(0.1) + Interval::point(0.2);
+        assert!(sum.contains(0.1 + 0.2));
+        assert!(sum.lower < sum.upper);
+        assert!(sum.contains(0.3));
+
+        let product = Interval::new(-1.0, 2.0) * Interval::new(3.0, 4.0);
+        assert!(product.lower <= -4.0 && product.upper >= 8.0);
+        assert!(product.width() < 12.0 + 1e-12);
+        assert_eq!(
+            Interval::point(0.0) * Interval::new(-3.0, 5.0),
+            Interval::point(0.0)
+        );
+    }
+
+    #[test]
+    fn test_functions()
+    {
+        let square = Interval::new(-1.0, 2.0).powi(2);
+        assert_eq!(square.lower, 0.0);
+        assert!(square.contains(4.0) && square.upper < 4.0 + 1e-12);
+        assert!(Interval::new(-2.0, -1.0).powi(3).contains(-8.0));
+        assert!(Interval::new(-2.0, -1.0).powi(3).contains(-1.0));
+
+        assert_eq!(
+            Interval::new(1.0, 2.0) / Interval::new(-1.0, 1.0),
+            Interval::ENTIRE
+        );
+        assert!(Interval::new(-2.0, -1.0).sqrt().is_empty());
+        assert_eq!(Interval::new(-1.0, 4.0).sqrt().lower, 0.0);
+
+        let sine = Interval::new(0.0, core::f64::consts::PI).sin();
+        assert_eq!(sine.upper, 1.0);
+        assert!(sine.lower <= 0.0 && sine.lower > -1e-12);
+        let cosine = Interval::new(-0.1, 0.1).cos();
+        assert_eq!(cosine.upper, 1.0);
+        assert!(cosine.contains(0.1f64.cos()));
+        assert_eq!(Interval::new(1.0, 2.0).tan(), Interval::ENTIRE);
+        assert!(Interval::new(0.5, 1.5).powf(0.5).contains(1.5f64.sqrt()));
+        assert!(Interval::new(-1.0, 1.0).ln().lower == f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_enclosures_of_value_and_derivative()
+    {
+        let domain = Interval::new(-0.5, 1.5);
+        let [enclosure] = Cubic.call([Variable::seeded_interval(domain)]);
+        assert_eq!(Cubic.call([domain]), [enclosure.value]);
+
+        for step in 0..=100 {
+            let x = domain.lower + domain.width() * step as f64 / 100.0;
+            let x = x.min(domain.upper);
+            let (values, jacobian) = evaluate_with(&Cubic, [x], Backend::Dual);
+            assert!(enclosure.value.contains(values[0]));
+            assert!(enclosure.derivative.contains(jacobian[0][0]));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_isolate_roots()
+    {
+        struct Square;
+
+        impl Objective<1, 1> for Square
+        {
+            fn call<T: Scalar>(&self, variables: [T; 1]) -> [T; 1]
+            {
+                [variables[0] * variables[0] - T::constant(2.0)]
+            }
+        }
+
+        let mut roots = isolate_roots(&Square, [Interval::new(-3.0, 3.0)], 1e-12);
+        roots.sort_by(|a, b| a.bounds[0].lower.total_cmp(&b.bounds[0].lower));
+        assert_eq!(roots.len(), 2);
+        for (root, expected) in roots.iter().zip([-(2.0f64.sqrt()), 2.0f64.sqrt()]) {
+            assert!(root.unique);
+            assert!(root.bounds[0].contains(expected));
+            assert!(root.bounds[0].width() < 1e-12);
+        }
+
+        assert!(isolate_roots(&Square, [Interval::new(2.0, 3.0)], 1e-12).is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_isolate_cube_roots_of_unity()
+    {
+        // The system from the newton fractals example, its roots are the cube roots of unity.
+        struct CubeRoots;
+
+        impl Objective<2, 2> for CubeRoots
+        {
+            fn call<T: Scalar>(&self, variables: [T; 2]) -> [T; 2]
+            {
+                let x = variables[0];
+                let y = variables[1];
+                let three = T::constant(3.0);
+                [
+                    x.powf(3.0) - three * x * y.powf(2.0) - T::constant(1.0),
+                    three * x.powf(2.0) * y - y.powf(3.0),
+                ]
+            }
+        }
+
+        let domain = [Interval::new(-2.0, 2.0), Interval::new(-2.0, 2.0)];
+        let roots = isolate_roots(&CubeRoots, domain, 1e-10);
+        assert_eq!(roots.len(), 3);
+
+        let half_root_three = 0.5 * 3.0f64.sqrt();
+        for expected in [
+            [1.0, 0.0],
+            [-0.5, half_root_three],
+            [-0.5, -half_root_three],
+        ] {
+            let matching: Vec<_> = roots
+                .iter()
+                .filter(|root| root.bounds[0].contains(expected[0]))
+                .filter(|root| root.bounds[1].contains(expected[1]))
+                .collect();
+            assert_eq!(matching.len(), 1);
+            assert!(matching[0].unique);
+        }
+    }
+
+    #[test]
+    fn test_display()
+    {
+        assert_eq!(format!("{}", Interval::new(1.0, 2.5)), "[1, 2.5]");
+        assert_eq!(format!("{:.2}", Interval::new(1.0, 2.5)), "[1.00, 2.50]");
+    }
+}
+
+#[cfg(test)]
+mod test_piecewise
+{
+    use super::evaluate::*;
+    use super::piecewise::*;
+    use super::variable::*;
+
+    fn piecewise(variables: [Variable; 2]) -> [Variable; 2]
+    {
+        let [x, y] = variables;
+        [
+            select(x.value > 1.0, x * y, y - x),
+            clamp(x, -1.0, 0.5) + max(x, y),
+        ]
+    }
+
+    #[test]
+    fn test_select()
+    {
+        let (values, jacobian) = evaluate(piecewise, [2.0, 3.0]);
+        assert_eq!(values, [6.0, 3.5]);
+        assert_eq!(jacobian, [[3.0, 2.0], [0.0, 1.0]]);
+
+        let (values, jacobian) = evaluate(piecewise, [0.0, -1.0]);
+        assert_eq!(values, [-1.0, 0.0]);
+        assert_eq!(jacobian, [[-1.0, 1.0], [2.0, 0.0]]);
+
+        let a = [Variable::seeded(1.0), Variable::with_derivative(2.0, 0.5)];
+        let b = [Variable::with_derivative(-1.0, 3.0), Variable::empty()];
+        let chosen = where_([false, true], a, b);
+        assert_eq!((chosen[0].value, chosen[0].derivative), (-1.0, 3.0));
+        assert_eq!((chosen[1].value, chosen[1].derivative), (2.0, 0.5));
+    }
+
+    #[test]
+    fn test_ties_average_derivatives()
+    {
+        let y = max(Variable::seeded(2.0), Variable::with_derivative(2.0, 0.0));
+        assert_eq!((y.value, y.derivative), (2.0, 0.5));
+        let y = min(Variable::seeded(2.0), Variable::with_derivative(2.0, 0.0));
+        assert_eq!((y.value, y.derivative), (2.0, 0.5));
+        let y = clamp(Variable::seeded(0.5), -1.0, 0.5);
+        assert_eq!((y.value, y.derivative), (0.5, 0.5));
+    }
+
+    #[test]
+    fn test_soft_max_approaches_max()
+    {
+        let a = Variable::seeded(1.0);
+        let b = Variable::with_derivative(0.5, 0.0);
+        for temperature in [1.0, 0.1, 0.01] {
+            let y = soft_max(a, b, temperature);
+            assert!(y.value >= 1.0 && y.value <= 1.0 + temperature * 2f64.ln());
+        }
+        let y = soft_max(a, b, 1e-3);
+        assert!(super::almost_eq(y.value, 1.0));
+        assert!(super::almost_eq(y.derivative, 1.0));
+
+        // Equal arguments share the derivative like max.
+        let y = soft_max(a, Variable::with_derivative(1.0, 0.0), 0.1);
+        assert!(super::almost_eq(y.derivative, 0.5));
+
+        // No overflow for values far beyond exp's range.
+        let y = soft_min(Variable::seeded(1000.0), Variable::seeded(-1000.0), 0.01);
+        assert!(super::almost_eq(y.value, -1000.0));
+        assert!(super::almost_eq(y.derivative, 1.0));
+    }
+
+    #[test]
+    fn test_smooth_surrogates()
+    {
+        let y = smooth_abs(Variable::seeded(0.0), 0.1);
+        assert_eq!((y.value, y.derivative), (0.0, 0.0));
+        let y = smooth_abs(Variable::seeded(-3.0), 0.01);
+        assert!((y.value - 3.0).abs() < 0.01);
+        assert!(super::almost_eq(
+            y.derivative,
+            -3.0 / (9.0f64 + 1e-4).sqrt()
+        ));
+
+        let inside = soft_clamp(Variable::seeded(0.0), -1.0, 1.0, 0.01);
+        assert!(super::almost_eq(inside.value, 0.0));
+        assert!(super::almost_eq(inside.derivative, 1.0));
+        let above = soft_clamp(Variable::seeded(3.0), -1.0, 1.0, 0.01);
+        assert!(super::almost_eq(above.value, 1.0));
+        assert!(above.derivative.abs() < 1e-12);
+
+        let condition = Variable::seeded(0.0);
+        let a = Variable::with_derivative(2.0, 0.0);
+        let b = Variable::with_derivative(-2.0, 0.0);
+        let y = soft_select(condition, a, b, 0.5);
+        assert_eq!(y.value, 0.0);
+        assert!(super::almost_eq(y.derivative, (2.0 - -2.0) * 0.25 / 0.5));
+        let y = soft_select(Variable::seeded(-800.0), a, b, 0.5);
+        assert_eq!((y.value, y.derivative), (-2.0, 0.0));
+    }
+
+    #[test]
+    fn test_smooth_derivatives_match_finite_differences()
+    {
+        fn smooth(variables: [Variable; 1]) -> [Variable; 3]
+        {
+            let x = variables[0];
+            [
+                soft_max(x, 2.0 * x - 0.3, 0.2),
+                smooth_abs(x - 0.1, 0.05),
+                soft_clamp(3.0 * x, -0.5, 0.5, 0.1),
+            ]
+        }
+
+        let step = 1e-6;
+        for x in [-0.4, 0.0, 0.15, 0.3, 0.7] {
+            let (_, jacobian) = evaluate(smooth, [x]);
+            let (forward, _) = evaluate(smooth, [x + step]);
+            let (backward, _) = evaluate(smooth, [x - step]);
+            for output in 0..3 {
+                let estimate = (forward[output] - backward[output]) / (2.0 * step);
+                assert!((jacobian[output][0] - estimate).abs() < 1e-6);
+            }
+        }
+    }
+}
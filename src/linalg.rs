@@ -0,0 +1,390 @@
+use core::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+use crate::scalar::{Real, Scalar};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vector<const N: usize, T = f64>(pub [T; N]);
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix<const ROWS: usize, const COLUMNS: usize, T = f64>(pub [[T; COLUMNS]; ROWS]);
+
+impl<const N: usize, T: Scalar> Vector<N, T>
+{
+    pub fn zeros() -> Self
+    {
+        Self([T::constant(0.0); N])
+    }
+
+    pub fn dot(&self, other: &Self) -> T
+    {
+        let mut sum = T::constant(0.0);
+        for (a, b) in self.0.into_iter().zip(other.0) {
+            sum = sum + a * b;
+        }
+        sum
+    }
+
+    pub fn norm_squared(&self) -> T
+    {
+        self.dot(self)
+    }
+
+    // The zero vector has norm 0 and, for dual numbers, derivative 0 instead of NaN since sqrt
+    // keeps a zero derivative at 0.
+    pub fn norm(&self) -> T
+    {
+        self.norm_squared().sqrt()
+    }
+
+    pub fn scale(&self, factor: T) -> Self
+    {
+        Self(self.0.map(|entry| entry * factor))
+    }
+}
+
+impl<const N: usize, T> From<[T; N]> for Vector<N, T>
+{
+    fn from(array: [T; N]) -> Self
+    {
+        Self(array)
+    }
+}
+
+impl<const N: usize, T> Index<usize> for Vector<N, T>
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T
+    {
+        &self.0[index]
+    }
+}
+
+impl<const N: usize, T> IndexMut<usize> for Vector<N, T>
+{
+    fn index_mut(&mut self, index: usize) -> &mut T
+    {
+        &mut self.0[index]
+    }
+}
+
+impl<const N: usize, T: Scalar> Add for Vector<N, T>
+{
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self
+    {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a = *a + b;
+        }
+        self
+    }
+}
+
+impl<const N: usize, T: Scalar> Sub for Vector<N, T>
+{
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self
+    {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a = *a - b;
+        }
+        self
+    }
+}
+
+impl<const N: usize, T: Scalar> Neg for Vector<N, T>
+{
+    type Output = Self;
+
+    fn neg(self) -> Self
+    {
+        Self(self.0.map(|entry| -entry))
+    }
+}
+
+impl<const ROWS: usize, const COLUMNS: usize, T: Scalar> Matrix<ROWS, COLUMNS, T>
+{
+    pub fn zeros() -> Self
+    {
+        Self([[T::constant(0.0); COLUMNS]; ROWS])
+    }
+
+    pub fn transpose(&self) -> Matrix<COLUMNS, ROWS, T>
+    {
+        let mut transposed = Matrix::zeros();
+        for (r, row) in self.0.iter().enumerate() {
+            for (c, &entry) in row.iter().enumerate() {
+                transposed.0[c][r] = entry;
+            }
+        }
+        transposed
+    }
+
+    pub fn row(&self, index: usize) -> Vector<COLUMNS, T>
+    {
+        Vector(self.0[index])
+    }
+
+    pub fn column(&self, index: usize) -> Vector<ROWS, T>
+    {
+        Vector(self.0.map(|row| row[index]))
+    }
+}
+
+impl<const N: usize, T: Scalar> Matrix<N, N, T>
+{
+    pub fn identity() -> Self
+    {
+        let mut identity = Self::zeros();
+        for i in 0..N {
+            identity.0[i][i] = T::constant(1.0);
+        }
+        identity
+    }
+}
+
+// Pivots at most this fraction of the largest entry count as zero when solving, the system is
+// too close to singular for the solution to mean anything.
+pub const SINGULAR_TOLERANCE: f64 = 1e-12;
+
+// LU decomposition with partial pivoting, stored in place. Returns the number of row swaps, or
+// None if a pivot is not larger than the tolerance in magnitude.
+fn decompose<const N: usize, const K: usize, T: Real>(
+    matrix: &mut [[T; N]; N],
+    rhs: &mut [[T; K]; N],
+    tolerance: f64,
+) -> Option<usize>
+{
+    let mut swaps = 0;
+    for column in 0..N {
+        let pivot = (column..N).max_by(|&a, &b| {
+            let a = matrix[a][column].value().abs();
+            let b = matrix[b][column].value().abs();
+            a.total_cmp(&b)
+        })?;
+        if matrix[pivot][column].value().abs() <= tolerance {
+            return None;
+        }
+        if pivot != column {
+            matrix.swap(column, pivot);
+            rhs.swap(column, pivot);
+            swaps += 1;
+        }
+
+        let pivot_row = matrix[column];
+        let pivot_rhs = rhs[column];
+        for row in column + 1..N {
+            let factor = matrix[row][column] / pivot_row[column];
+            for (entry, pivot_entry) in matrix[row].iter_mut().zip(pivot_row).skip(column) {
+                *entry = *entry - factor * pivot_entry;
+            }
+            for (entry, pivot_entry) in rhs[row].iter_mut().zip(pivot_rhs) {
+                *entry = *entry - factor * pivot_entry;
+            }
+        }
+    }
+    Some(swaps)
+}
+
+impl<const N: usize, T: Real> Matrix<N, N, T>
+{
+    // A singular matrix has determinant 0 but not necessarily derivative 0, e.g. det[[x, 0], [0, 1]]
+    // at x = 0, so a zero pivot falls back to an expansion which does not divide by it.
+    pub fn determinant(&self) -> T
+    {
+        let mut matrix = self.0;
+        let mut rhs: [[T; 0]; N] = [[]; N];
+        let Some(swaps) = decompose(&mut matrix, &mut rhs, 0.0) else {
+            return self.characteristic_determinant();
+        };
+
+        let mut determinant = T::constant(if swaps % 2 == 0 { 1.0 } else { -1.0 });
+        for (i, row) in matrix.iter().enumerate() {
+            determinant = determinant * row[i];
+        }
+        determinant
+    }
+
+    // None if the matrix is singular or within SINGULAR_TOLERANCE of it.
+    pub fn solve_matrix<const K: usize>(&self, rhs: Matrix<N, K, T>) -> Option<Matrix<N, K, T>>
+    {
+        let scale = self
+            .0
+            .iter()
+            .flatten()
+            .map(|entry| entry.value().abs())
+            .fold(0.0, f64::max);
+        let mut matrix = self.0;
+        let mut rhs = rhs.0;
+        decompose(&mut matrix, &mut rhs, SINGULAR_TOLERANCE * scale)?;
+
+        for row in (0..N).rev() {
+            for column in row + 1..N {
+                let solved = rhs[column];
+                for (entry, solved_entry) in rhs[row].iter_mut().zip(solved) {
+                    *entry = *entry - matrix[row][column] * solved_entry;
+                }
+            }
+            for entry in rhs[row].iter_mut() {
+                *entry = *entry / matrix[row][row];
+            }
+        }
+        Some(Matrix(rhs))
+    }
+
+    // Faddeev-LeVerrier: the coefficients of the characteristic polynomial, the last of which is
+    // the determinant up to sign. It only divides by integers, so it stays exact for dual numbers
+    // at singular matrices. O(N⁴) and less stable than elimination, only used where that fails.
+    fn characteristic_determinant(&self) -> T
+    {
+        let mut power = Self::zeros();
+        let mut coefficient = T::constant(1.0);
+        for k in 1..=N {
+            power = *self * power;
+            for i in 0..N {
+                power.0[i][i] = power.0[i][i] + coefficient;
+            }
+            let product = *self * power;
+            let mut trace = T::constant(0.0);
+            for i in 0..N {
+                trace = trace + product.0[i][i];
+            }
+            coefficient = -trace / T::constant(k as f64);
+        }
+        if N.is_multiple_of(2) {
+            coefficient
+        } else {
+            -coefficient
+        }
+    }
+
+    pub fn solve(&self, rhs: Vector<N, T>) -> Option<Vector<N, T>>
+    {
+        let rhs = Matrix(rhs.0.map(|entry| [entry]));
+        let solution = self.solve_matrix(rhs)?;
+        Some(solution.column(0))
+    }
+
+    pub fn inverse(&self) -> Option<Self>
+    {
+        self.solve_matrix(Self::identity())
+    }
+
+    // Lower triangular L with L Lᵀ = self for symmetric positive semidefinite matrices, None if
+    // the matrix is not symmetric or not semidefinite. Pivots within rounding of zero give a zero
+    // column, so singular covariances, e.g. of exactly known inputs, still decompose, as long as
+    // the rest of the column is also zero.
+    pub fn cholesky(&self) -> Option<Self>
+    {
+        let scale = self
+            .0
+            .iter()
+            .flatten()
+            .map(|entry| entry.value().abs())
+            .fold(0.0, f64::max);
+        let tolerance = N as f64 * f64::EPSILON * scale;
+
+        for row in 0..N {
+            for column in 0..row {
+                if (self.0[row][column].value() - self.0[column][row].value()).abs() > tolerance {
+                    return None;
+                }
+            }
+        }
+
+        let mut lower = Self::zeros();
+        for column in 0..N {
+            let mut pivot = self.0[column][column];
+            for k in 0..column {
+                pivot = pivot - lower.0[column][k] * lower.0[column][k];
+            }
+            if pivot.value() < -tolerance {
+                return None;
+            }
+
+            let root = if pivot.value() > tolerance {
+                let root = pivot.sqrt();
+                lower.0[column][column] = root;
+                Some(root)
+            } else {
+                None
+            };
+            for row in column + 1..N {
+                let mut entry = self.0[row][column];
+                for k in 0..column {
+                    entry = entry - lower.0[row][k] * lower.0[column][k];
+                }
+                match root {
+                    Some(root) => lower.0[row][column] = entry / root,
+                    // A zero pivot with a nonzero entry below it is a 2x2 minor with a negative
+                    // determinant.
+                    None if entry.value().abs() > tolerance => return None,
+                    None => {}
+                }
+            }
+        }
+        Some(lower)
+    }
+}
+
+impl<const ROWS: usize, const COLUMNS: usize, T> From<[[T; COLUMNS]; ROWS]>
+    for Matrix<ROWS, COLUMNS, T>
+{
+    fn from(array: [[T; COLUMNS]; ROWS]) -> Self
+    {
+        Self(array)
+    }
+}
+
+impl<const ROWS: usize, const COLUMNS: usize, T> Index<(usize, usize)> for Matrix<ROWS, COLUMNS, T>
+{
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &T
+    {
+        &self.0[row][column]
+    }
+}
+
+impl<const ROWS: usize, const COLUMNS: usize, T> IndexMut<(usize, usize)>
+    for Matrix<ROWS, COLUMNS, T>
+{
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut T
+    {
+        &mut self.0[row][column]
+    }
+}
+
+impl<const ROWS: usize, const INNER: usize, const COLUMNS: usize, T: Scalar>
+    Mul<Matrix<INNER, COLUMNS, T>> for Matrix<ROWS, INNER, T>
+{
+    type Output = Matrix<ROWS, COLUMNS, T>;
+
+    fn mul(self, other: Matrix<INNER, COLUMNS, T>) -> Self::Output
+    {
+        let mut product = Matrix::zeros();
+        for r in 0..ROWS {
+            for c in 0..COLUMNS {
+                product.0[r][c] = self.row(r).dot(&other.column(c));
+            }
+        }
+        product
+    }
+}
+
+impl<const ROWS: usize, const COLUMNS: usize, T: Scalar> Mul<Vector<COLUMNS, T>>
+    for Matrix<ROWS, COLUMNS, T>
+{
+    type Output = Vector<ROWS, T>;
+
+    fn mul(self, vector: Vector<COLUMNS, T>) -> Self::Output
+    {
+        let mut product = Vector::zeros();
+        for r in 0..ROWS {
+            product.0[r] = self.row(r).dot(&vector);
+        }
+        product
+    }
+}
@@ -0,0 +1,84 @@
+// The f64 math functions the crate needs. They come from the standard library, or from libm when
+// the libm feature is enabled, which also works without std.
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("autocrab needs either the std or the libm feature for its math functions");
+
+macro_rules! unary {
+    ($($name:ident => $libm:ident),* $(,)?) => {
+        $(
+            #[cfg(feature = "libm")]
+            #[inline]
+            pub fn $name(x: f64) -> f64
+            {
+                libm::$libm(x)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "libm")))]
+            #[inline]
+            pub fn $name(x: f64) -> f64
+            {
+                f64::$name(x)
+            }
+        )*
+    };
+}
+
+macro_rules! binary {
+    ($($name:ident => $libm:ident),* $(,)?) => {
+        $(
+            #[cfg(feature = "libm")]
+            #[inline]
+            pub fn $name(x: f64, y: f64) -> f64
+            {
+                libm::$libm(x, y)
+            }
+
+            #[cfg(all(feature = "std", not(feature = "libm")))]
+            #[inline]
+            pub fn $name(x: f64, y: f64) -> f64
+            {
+                f64::$name(x, y)
+            }
+        )*
+    };
+}
+
+unary! {
+    sqrt => sqrt,
+    sin => sin,
+    cos => cos,
+    tan => tan,
+    ln => log,
+    exp => exp,
+    sinh => sinh,
+    cosh => cosh,
+    floor => floor,
+    ceil => ceil,
+}
+
+binary! {
+    powf => pow,
+    atan2 => atan2,
+    hypot => hypot,
+}
+
+// Exponentiation by squaring, the same on every backend.
+pub fn powi(x: f64, exponent: i32) -> f64
+{
+    let mut base = x;
+    let mut remaining = exponent.unsigned_abs();
+    let mut result = 1.0;
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        remaining >>= 1;
+    }
+    if exponent < 0 {
+        1.0 / result
+    } else {
+        result
+    }
+}
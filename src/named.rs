@@ -0,0 +1,220 @@
+use core::fmt;
+use core::ops::Index;
+
+use super::evaluate::*;
+use super::table::*;
+
+fn position(names: &[&str], name: &str) -> Option<usize>
+{
+    names.iter().position(|&candidate| candidate == name)
+}
+
+fn assert_unique(names: &[&str])
+{
+    for (index, name) in names.iter().enumerate() {
+        assert!(
+            !names[..index].contains(name),
+            "name {:?} is used twice",
+            name
+        );
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NameError<'a>
+{
+    Unknown(&'a str),
+    Missing(&'a str),
+    Duplicate(&'a str),
+}
+
+impl fmt::Display for NameError<'_>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            NameError::Unknown(name) => write!(f, "unknown input {:?}", name),
+            NameError::Missing(name) => write!(f, "no value for input {:?}", name),
+            NameError::Duplicate(name) => write!(f, "input {:?} given twice", name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NameError<'_> {}
+
+// Derivatives of one output, indexed by input name.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Derivatives<'a, const VARIABLE_COUNT: usize>
+{
+    pub names: [&'a str; VARIABLE_COUNT],
+    pub derivatives: [f64; VARIABLE_COUNT],
+}
+
+impl<const VARIABLE_COUNT: usize> Derivatives<'_, VARIABLE_COUNT>
+{
+    pub fn get(&self, input: &str) -> Option<f64>
+    {
+        position(&self.names, input).map(|index| self.derivatives[index])
+    }
+}
+
+impl<const VARIABLE_COUNT: usize> Index<&str> for Derivatives<'_, VARIABLE_COUNT>
+{
+    type Output = f64;
+
+    fn index(&self, input: &str) -> &f64
+    {
+        match position(&self.names, input) {
+            Some(index) => &self.derivatives[index],
+            None => panic!("unknown input {:?}", input),
+        }
+    }
+}
+
+// Result of evaluating a NamedObjective, `result["flow"]["pressure"]` is the derivative of the
+// output flow with respect to the input pressure.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NamedJacobian<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    pub input_names: [&'a str; VARIABLE_COUNT],
+    pub output_names: [&'a str; OUTPUT_COUNT],
+    pub values: [f64; OUTPUT_COUNT],
+    pub jacobian: [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    rows: [Derivatives<'a, VARIABLE_COUNT>; OUTPUT_COUNT],
+}
+
+impl<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+    NamedJacobian<'a, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    pub fn new(
+        input_names: [&'a str; VARIABLE_COUNT],
+        output_names: [&'a str; OUTPUT_COUNT],
+        values: [f64; OUTPUT_COUNT],
+        jacobian: [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    ) -> Self
+    {
+        Self {
+            input_names,
+            output_names,
+            values,
+            jacobian,
+            rows: jacobian.map(|derivatives| Derivatives {
+                names: input_names,
+                derivatives,
+            }),
+        }
+    }
+
+    pub fn value(&self, output: &str) -> Option<f64>
+    {
+        position(&self.output_names, output).map(|index| self.values[index])
+    }
+
+    pub fn get(&self, output: &str, input: &str) -> Option<f64>
+    {
+        position(&self.output_names, output).and_then(|index| self.rows[index].get(input))
+    }
+
+    pub fn table(&self) -> JacobianTable<'_, VARIABLE_COUNT, OUTPUT_COUNT>
+    {
+        JacobianTable::new(&self.jacobian)
+            .with_values(&self.values)
+            .with_input_labels(self.input_names)
+            .with_output_labels(self.output_names)
+    }
+}
+
+impl<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize> Index<&str>
+    for NamedJacobian<'a, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    type Output = Derivatives<'a, VARIABLE_COUNT>;
+
+    fn index(&self, output: &str) -> &Derivatives<'a, VARIABLE_COUNT>
+    {
+        match position(&self.output_names, output) {
+            Some(index) => &self.rows[index],
+            None => panic!("unknown output {:?}", output),
+        }
+    }
+}
+
+// Objective function with named inputs and outputs, names have to be unique.
+#[derive(Debug, Copy, Clone)]
+pub struct NamedObjective<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
+    input_names: [&'a str; VARIABLE_COUNT],
+    output_names: [&'a str; OUTPUT_COUNT],
+}
+
+impl<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+    NamedObjective<'a, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    pub fn new(
+        function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
+        input_names: [&'a str; VARIABLE_COUNT],
+        output_names: [&'a str; OUTPUT_COUNT],
+    ) -> Self
+    {
+        assert_unique(&input_names);
+        assert_unique(&output_names);
+        Self {
+            function,
+            input_names,
+            output_names,
+        }
+    }
+
+    pub fn input_names(&self) -> [&'a str; VARIABLE_COUNT]
+    {
+        self.input_names
+    }
+
+    pub fn output_names(&self) -> [&'a str; OUTPUT_COUNT]
+    {
+        self.output_names
+    }
+
+    // Orders named values like the inputs, every input needs exactly one value.
+    pub fn point<'b>(
+        &self,
+        values: &[(&'b str, f64)],
+    ) -> Result<[f64; VARIABLE_COUNT], NameError<'b>>
+    where
+        'a: 'b,
+    {
+        let mut x = [None; VARIABLE_COUNT];
+        for &(name, value) in values {
+            let index = position(&self.input_names, name).ok_or(NameError::Unknown(name))?;
+            if x[index].replace(value).is_some() {
+                return Err(NameError::Duplicate(name));
+            }
+        }
+
+        let mut point = [0.0; VARIABLE_COUNT];
+        for (index, value) in x.into_iter().enumerate() {
+            point[index] = value.ok_or(NameError::Missing(self.input_names[index]))?;
+        }
+        Ok(point)
+    }
+
+    pub fn evaluate(
+        &self,
+        x: [f64; VARIABLE_COUNT],
+    ) -> NamedJacobian<'a, VARIABLE_COUNT, OUTPUT_COUNT>
+    {
+        let (values, jacobian) = evaluate(self.function, x);
+        NamedJacobian::new(self.input_names, self.output_names, values, jacobian)
+    }
+
+    pub fn evaluate_named<'b>(
+        &self,
+        values: &[(&'b str, f64)],
+    ) -> Result<NamedJacobian<'a, VARIABLE_COUNT, OUTPUT_COUNT>, NameError<'b>>
+    where
+        'a: 'b,
+    {
+        Ok(self.evaluate(self.point(values)?))
+    }
+}
@@ -0,0 +1,112 @@
+use crate::functions::{exp, ln, sqrt};
+use crate::math;
+use crate::variable::Variable;
+
+// Branching on `variable.value` with if/else works, the derivative of the taken branch carries
+// through. These helpers make that explicit, and the soft_ variants below replace the kinks with
+// smooth surrogates for optimisers that need continuous derivatives.
+
+// a where condition holds, otherwise b, with the derivative of the chosen branch.
+pub fn select(condition: bool, a: Variable, b: Variable) -> Variable
+{
+    if condition {
+        a
+    } else {
+        b
+    }
+}
+
+// Elementwise select.
+pub fn where_<const N: usize>(
+    conditions: [bool; N],
+    a: [Variable; N],
+    b: [Variable; N],
+) -> [Variable; N]
+{
+    core::array::from_fn(|i| select(conditions[i], a[i], b[i]))
+}
+
+// Where both arguments are equal the derivative is the average of theirs, the midpoint of the
+// subgradient like abs at 0.
+pub fn max(a: Variable, b: Variable) -> Variable
+{
+    if a.value > b.value {
+        a
+    } else if a.value < b.value {
+        b
+    } else {
+        Variable::with_derivative(a.value, 0.5 * (a.derivative + b.derivative))
+    }
+}
+
+pub fn min(a: Variable, b: Variable) -> Variable
+{
+    -max(-a, -b)
+}
+
+pub fn clamp(variable: Variable, lower: f64, upper: f64) -> Variable
+{
+    assert!(lower <= upper);
+    min(
+        max(variable, Variable::with_derivative(lower, 0.0)),
+        Variable::with_derivative(upper, 0.0),
+    )
+}
+
+// The smooth variants take a temperature, the width of the region around a kink that is rounded
+// off. They approach their exact counterparts as the temperature goes to 0.
+
+// T ln(exp(a / T) + exp(b / T)), shifted by the larger value so it does not overflow. Lies above
+// max(a, b) by at most T ln 2.
+pub fn soft_max(a: Variable, b: Variable, temperature: f64) -> Variable
+{
+    assert!(temperature > 0.0);
+    let shift = a.value.max(b.value);
+    let sum = exp((a - shift) / temperature) + exp((b - shift) / temperature);
+    shift + temperature * ln(sum)
+}
+
+pub fn soft_min(a: Variable, b: Variable, temperature: f64) -> Variable
+{
+    -soft_max(-a, -b, temperature)
+}
+
+// sqrt(x² + T²) - T, zero with zero derivative at 0 and within T of |x| everywhere.
+pub fn smooth_abs(variable: Variable, temperature: f64) -> Variable
+{
+    assert!(temperature > 0.0);
+    sqrt(variable * variable + temperature * temperature) - temperature
+}
+
+// Logistic blend of a and b, a for condition well above 0 and b well below it.
+pub fn soft_select(condition: Variable, a: Variable, b: Variable, temperature: f64) -> Variable
+{
+    assert!(temperature > 0.0);
+    let weight = sigmoid(condition / temperature);
+    weight * a + (1.0 - weight) * b
+}
+
+pub fn soft_clamp(variable: Variable, lower: f64, upper: f64, temperature: f64) -> Variable
+{
+    assert!(lower <= upper);
+    soft_min(
+        soft_max(variable, Variable::with_derivative(lower, 0.0), temperature),
+        Variable::with_derivative(upper, 0.0),
+        temperature,
+    )
+}
+
+// 1 / (1 + exp(-x)), evaluated on the side where the exponential cannot overflow.
+fn sigmoid(variable: Variable) -> Variable
+{
+    let value = if variable.value >= 0.0 {
+        1.0 / (1.0 + math::exp(-variable.value))
+    } else {
+        let exponential = math::exp(variable.value);
+        exponential / (1.0 + exponential)
+    };
+    Variable {
+        value,
+        derivative: variable.derivative * value * (1.0 - value),
+    }
+}
@@ -0,0 +1,253 @@
+use super::evaluate::*;
+use super::math;
+use super::variable::*;
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Estimate
+{
+    pub value: f64,
+    pub error: f64,
+}
+
+pub fn linspace(start: f64, end: f64, steps: usize) -> Vec<f64>
+{
+    assert!(start <= end);
+    assert!(steps > 0);
+    let delta = (end - start) / steps as f64;
+    (0..=steps)
+        .map(|i| {
+            if i == steps {
+                end
+            } else {
+                start + (i as f64) * delta
+            }
+        })
+        .collect()
+}
+
+fn value_at(function: ObjectiveFunction<1, 1>, x: f64) -> f64
+{
+    function([Variable::with_derivative(x, 0.0)])[0].value
+}
+
+fn value_and_derivative_at(function: ObjectiveFunction<1, 1>, x: f64) -> (f64, f64)
+{
+    let (values, jacobian) = evaluate(function, [x]);
+    (values[0], jacobian[0][0])
+}
+
+pub fn trapezoidal(function: ObjectiveFunction<1, 1>, start: f64, end: f64, steps: usize) -> f64
+{
+    let delta = (end - start) / steps as f64;
+    let range = linspace(start, end, steps);
+    let last = range.len() - 1;
+
+    let mut integral = 0.0;
+    for (i, x) in range.into_iter().enumerate() {
+        let weight = if i == 0 || i == last { 0.5 } else { 1.0 };
+        integral += weight * value_at(function, x);
+    }
+    integral * delta
+}
+
+// Corrected trapezoidal rule. Integrating the cubic Hermite interpolant on every interval adds
+// delta^2 / 12 * (f'(a) - f'(b)) per interval, the interior terms telescope so only the
+// derivatives at the end points are needed.
+pub fn hermite(function: ObjectiveFunction<1, 1>, start: f64, end: f64, steps: usize) -> f64
+{
+    let delta = (end - start) / steps as f64;
+    let (_, start_derivative) = value_and_derivative_at(function, start);
+    let (_, end_derivative) = value_and_derivative_at(function, end);
+    let correction = delta * delta / 12.0 * (start_derivative - end_derivative);
+    trapezoidal(function, start, end, steps) + correction
+}
+
+pub fn simpson(function: ObjectiveFunction<1, 1>, start: f64, end: f64, steps: usize) -> f64
+{
+    assert!(
+        steps.is_multiple_of(2),
+        "simpson requires an even number of steps"
+    );
+    let delta = (end - start) / steps as f64;
+    let range = linspace(start, end, steps);
+    let last = range.len() - 1;
+
+    let mut integral = 0.0;
+    for (i, x) in range.into_iter().enumerate() {
+        let weight = if i == 0 || i == last {
+            1.0
+        } else if i % 2 == 1 {
+            4.0
+        } else {
+            2.0
+        };
+        integral += weight * value_at(function, x);
+    }
+    integral * delta / 3.0
+}
+
+fn legendre(order: usize, x: Variable) -> Variable
+{
+    let mut previous = Variable::with_derivative(1.0, 0.0);
+    let mut current = x;
+    if order == 0 {
+        return previous;
+    }
+    for n in 1..order {
+        let n = n as f64;
+        let next = ((2.0 * n + 1.0) * x * current - n * previous) / (n + 1.0);
+        previous = current;
+        current = next;
+    }
+    current
+}
+
+// Nodes and weights on [-1, 1]. The nodes are found with Newton's method on the Legendre
+// polynomial, using autocrab itself for the derivative.
+pub fn gauss_legendre_nodes(points: usize) -> Vec<(f64, f64)>
+{
+    assert!(points > 0);
+    let n = points as f64;
+    let mut nodes = Vec::with_capacity(points);
+    for i in 0..points {
+        let mut x = math::cos(std::f64::consts::PI * (i as f64 + 0.75) / (n + 0.5));
+        for _ in 0..100 {
+            let p = legendre(points, Variable::seeded(x));
+            let step = p.value / p.derivative;
+            x -= step;
+            if step.abs() < 1.0e-15 {
+                break;
+            }
+        }
+        let derivative = legendre(points, Variable::seeded(x)).derivative;
+        let weight = 2.0 / ((1.0 - x * x) * derivative * derivative);
+        nodes.push((x, weight));
+    }
+    nodes
+}
+
+pub fn gauss_legendre(function: ObjectiveFunction<1, 1>, start: f64, end: f64, points: usize)
+    -> f64
+{
+    let half_width = 0.5 * (end - start);
+    let center = 0.5 * (end + start);
+    let mut integral = 0.0;
+    for (node, weight) in gauss_legendre_nodes(points) {
+        integral += weight * value_at(function, center + half_width * node);
+    }
+    integral * half_width
+}
+
+fn simpson_step(start: (f64, f64), middle: (f64, f64), end: (f64, f64)) -> f64
+{
+    (end.0 - start.0) / 6.0 * (start.1 + 4.0 * middle.1 + end.1)
+}
+
+fn adaptive_simpson_step(
+    function: ObjectiveFunction<1, 1>,
+    start: (f64, f64),
+    middle: (f64, f64),
+    end: (f64, f64),
+    whole: f64,
+    tolerance: f64,
+    depth: usize,
+) -> Estimate
+{
+    let left_x = 0.5 * (start.0 + middle.0);
+    let right_x = 0.5 * (middle.0 + end.0);
+    let left_middle = (left_x, value_at(function, left_x));
+    let right_middle = (right_x, value_at(function, right_x));
+
+    let left = simpson_step(start, left_middle, middle);
+    let right = simpson_step(middle, right_middle, end);
+    let difference = left + right - whole;
+
+    // Below rounding of the local estimate the difference is noise, subdividing further does not
+    // improve it.
+    let tolerance = tolerance.max(f64::EPSILON * (left + right).abs());
+    if depth == 0 || difference.abs() <= 15.0 * tolerance {
+        return Estimate {
+            value: left + right + difference / 15.0,
+            error: difference.abs() / 15.0,
+        };
+    }
+
+    let left = adaptive_simpson_step(
+        function,
+        start,
+        left_middle,
+        middle,
+        left,
+        0.5 * tolerance,
+        depth - 1,
+    );
+    let right = adaptive_simpson_step(
+        function,
+        middle,
+        right_middle,
+        end,
+        right,
+        0.5 * tolerance,
+        depth - 1,
+    );
+    Estimate {
+        value: left.value + right.value,
+        error: left.error + right.error,
+    }
+}
+
+// Deepest subdivision, at most 2^MAX_DEPTH intervals however small the tolerance.
+pub const MAX_DEPTH: usize = 20;
+
+pub fn adaptive_simpson(
+    function: ObjectiveFunction<1, 1>,
+    start: f64,
+    end: f64,
+    tolerance: f64,
+) -> Estimate
+{
+    let middle_x = 0.5 * (start + end);
+    let start = (start, value_at(function, start));
+    let middle = (middle_x, value_at(function, middle_x));
+    let end = (end, value_at(function, end));
+    let whole = simpson_step(start, middle, end);
+    adaptive_simpson_step(function, start, middle, end, whole, tolerance, MAX_DEPTH)
+}
+
+// Differentiates the integral with respect to whatever the integrand and the bounds depend on,
+// following the Leibniz integral rule. The integrand is called with constant x, so the derivative
+// carried by its result is the partial derivative with respect to the parameters.
+pub fn leibniz<F>(integrand: F, start: Variable, end: Variable, steps: usize) -> Variable
+where
+    F: Fn(Variable) -> Variable,
+{
+    assert!(
+        steps.is_multiple_of(2),
+        "leibniz requires an even number of steps"
+    );
+    let delta = (end.value - start.value) / steps as f64;
+
+    let mut integral = Variable::empty();
+    for i in 0..=steps {
+        let x = if i == steps {
+            end.value
+        } else {
+            start.value + (i as f64) * delta
+        };
+        let weight = if i == 0 || i == steps {
+            1.0
+        } else if i % 2 == 1 {
+            4.0
+        } else {
+            2.0
+        };
+        integral += weight * integrand(Variable::with_derivative(x, 0.0));
+    }
+    integral *= delta / 3.0;
+
+    let start_value = integrand(Variable::with_derivative(start.value, 0.0)).value;
+    let end_value = integrand(Variable::with_derivative(end.value, 0.0)).value;
+    integral.derivative += end_value * end.derivative - start_value * start.derivative;
+    integral
+}
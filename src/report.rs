@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+
+use super::evaluate::*;
+
+// Result of one evaluation together with the point it was evaluated at, in plain vectors so it can
+// be stored and exchanged without knowing the dimensions at compile time. With the serde feature
+// it serializes to
+//
+// {
+//     "input": [x0, x1, ...],
+//     "values": [y0, y1, ...],
+//     "jacobian": [[dy0/dx0, dy0/dx1, ...], [dy1/dx0, ...], ...],
+//     "metadata": {"key": "value", ...}
+// }
+//
+// jacobian has one row per value and one column per input. Metadata keys are sorted. JSON has no
+// NaN or infinity, so in human readable formats those are written as the strings "NaN", "inf" and
+// "-inf". Binary formats like bincode keep plain f64.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvaluationReport
+{
+    #[cfg_attr(feature = "serde", serde(with = "numbers"))]
+    pub input: Vec<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "numbers"))]
+    pub values: Vec<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "rows"))]
+    pub jacobian: Vec<Vec<f64>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl EvaluationReport
+{
+    pub fn new<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+        input: [f64; VARIABLE_COUNT],
+        values: [f64; OUTPUT_COUNT],
+        jacobian: [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    ) -> Self
+    {
+        Self {
+            input: input.to_vec(),
+            values: values.to_vec(),
+            jacobian: jacobian.iter().map(|row| row.to_vec()).collect(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    pub fn evaluate<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+        function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
+        x: [f64; VARIABLE_COUNT],
+    ) -> Self
+    {
+        let (values, jacobian) = evaluate(function, x);
+        Self::new(x, values, jacobian)
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self
+    {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    // Back to the fixed size arrays, None if the dimensions do not match.
+    pub fn to_arrays<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+        &self,
+    ) -> Option<([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT])>
+    {
+        if self.input.len() != VARIABLE_COUNT || self.jacobian.len() != OUTPUT_COUNT {
+            return None;
+        }
+        let values = self.values.as_slice().try_into().ok()?;
+        let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
+        for (row, source) in jacobian.iter_mut().zip(&self.jacobian) {
+            *row = source.as_slice().try_into().ok()?;
+        }
+        Some((values, jacobian))
+    }
+}
+
+// A number that is written as a string when it is not finite and the format is human readable,
+// like JSON which has no NaN or infinity. Binary formats store every f64 as is.
+#[cfg(feature = "serde")]
+#[derive(Copy, Clone)]
+struct Number(f64);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Number
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_f64(self.0);
+        }
+        match self.0 {
+            value if value.is_nan() => serializer.serialize_str("NaN"),
+            f64::INFINITY => serializer.serialize_str("inf"),
+            f64::NEG_INFINITY => serializer.serialize_str("-inf"),
+            value => serializer.serialize_f64(value),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Number
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor
+        {
+            type Value = Number;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result
+            {
+                formatter.write_str("a number or one of \"NaN\", \"inf\", \"-inf\"")
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Number, E>
+            {
+                Ok(Number(value))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Number, E>
+            {
+                Ok(Number(value as f64))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Number, E>
+            {
+                Ok(Number(value as f64))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Number, E>
+            {
+                match value {
+                    "NaN" => Ok(Number(f64::NAN)),
+                    "inf" => Ok(Number(f64::INFINITY)),
+                    "-inf" => Ok(Number(f64::NEG_INFINITY)),
+                    _ => Err(E::invalid_value(serde::de::Unexpected::Str(value), &self)),
+                }
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(Visitor)
+        } else {
+            deserializer.deserialize_f64(Visitor)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod numbers
+{
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Number;
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.collect_seq(values.iter().map(|&value| Number(value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error>
+    {
+        let numbers = Vec::<Number>::deserialize(deserializer)?;
+        Ok(numbers.into_iter().map(|number| number.0).collect())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod rows
+{
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Number;
+
+    pub fn serialize<S: Serializer>(rows: &[Vec<f64>], serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.collect_seq(
+            rows.iter()
+                .map(|row| row.iter().map(|&value| Number(value)).collect::<Vec<_>>()),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<f64>>, D::Error>
+    {
+        let rows = Vec::<Vec<Number>>::deserialize(deserializer)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|number| number.0).collect())
+            .collect())
+    }
+}
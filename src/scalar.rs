@@ -0,0 +1,190 @@
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::complex::Complex;
+use crate::custom::{ChainRule, CustomFn};
+use crate::functions;
+use crate::math;
+use crate::variable::{self, Variable};
+
+pub trait Scalar:
+    ChainRule
+    + Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn constant(value: f64) -> Self;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn ln(self) -> Self;
+    fn exp(self) -> Self;
+    fn powf(self, exponent: f64) -> Self;
+}
+
+// Scalars with a single real primal value, which is what pivoting and branching look at.
+pub trait Real: Scalar
+{
+    fn value(&self) -> f64;
+}
+
+impl Scalar for f64
+{
+    fn constant(value: f64) -> Self
+    {
+        value
+    }
+
+    fn sqrt(self) -> Self
+    {
+        math::sqrt(self)
+    }
+
+    fn sin(self) -> Self
+    {
+        math::sin(self)
+    }
+
+    fn cos(self) -> Self
+    {
+        math::cos(self)
+    }
+
+    fn tan(self) -> Self
+    {
+        math::tan(self)
+    }
+
+    fn ln(self) -> Self
+    {
+        math::ln(self)
+    }
+
+    fn exp(self) -> Self
+    {
+        math::exp(self)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        math::powf(self, exponent)
+    }
+}
+
+impl Real for f64
+{
+    fn value(&self) -> f64
+    {
+        *self
+    }
+}
+
+impl Scalar for Variable
+{
+    fn constant(value: f64) -> Self
+    {
+        Variable::with_derivative(value, 0.0)
+    }
+
+    fn sqrt(self) -> Self
+    {
+        functions::sqrt(self)
+    }
+
+    fn sin(self) -> Self
+    {
+        functions::sin(self)
+    }
+
+    fn cos(self) -> Self
+    {
+        functions::cos(self)
+    }
+
+    fn tan(self) -> Self
+    {
+        functions::tan(self)
+    }
+
+    fn ln(self) -> Self
+    {
+        functions::ln(self)
+    }
+
+    fn exp(self) -> Self
+    {
+        functions::exp(self)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        functions::pow(self, exponent)
+    }
+}
+
+impl Real for Variable
+{
+    fn value(&self) -> f64
+    {
+        self.value
+    }
+}
+
+// First order like the complex step, the imaginary part is carried by the derivative at the real
+// part.
+impl ChainRule for Complex
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        Complex::new(
+            function.value(self.re),
+            variable::chain(self.im, function.derivative(self.re)),
+        )
+    }
+}
+
+impl Scalar for Complex
+{
+    fn constant(value: f64) -> Self
+    {
+        Complex::from(value)
+    }
+
+    fn sqrt(self) -> Self
+    {
+        Complex::sqrt(&self)
+    }
+
+    fn sin(self) -> Self
+    {
+        Complex::sin(&self)
+    }
+
+    fn cos(self) -> Self
+    {
+        Complex::cos(&self)
+    }
+
+    fn tan(self) -> Self
+    {
+        Complex::tan(&self)
+    }
+
+    fn ln(self) -> Self
+    {
+        Complex::ln(&self)
+    }
+
+    fn exp(self) -> Self
+    {
+        Complex::exp(&self)
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        Complex::powf(&self, exponent)
+    }
+}
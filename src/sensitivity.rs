@@ -0,0 +1,376 @@
+use core::fmt::{self, Display};
+
+use super::evaluate::*;
+use super::table::*;
+
+// Relative sensitivities of the outputs of an objective around a point, computed from the jacobian
+// and the input and output values.
+//
+// The elasticity ∂ln y/∂ln x = J x / y is the relative change of an output for a relative change
+// of an input. It is undefined for outputs that are zero, those give None. Inputs that are zero
+// have elasticity zero, a relative change of a zero input does not move it.
+//
+// The normalised sensitivity J s is the change of an output when an input moves by its scale s.
+// The scale defaults to |x|, which gives the same ranking as the elasticities, but can be set to
+// e.g. standard deviations or tolerances so inputs at zero still get a meaningful sensitivity.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sensitivity<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    pub input: [f64; VARIABLE_COUNT],
+    pub values: [f64; OUTPUT_COUNT],
+    pub jacobian: [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    pub scales: [f64; VARIABLE_COUNT],
+    input_labels: Option<[&'a str; VARIABLE_COUNT]>,
+    output_labels: Option<[&'a str; OUTPUT_COUNT]>,
+}
+
+impl<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+    Sensitivity<'a, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    pub fn new(
+        input: [f64; VARIABLE_COUNT],
+        values: [f64; OUTPUT_COUNT],
+        jacobian: [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    ) -> Self
+    {
+        Self {
+            input,
+            values,
+            jacobian,
+            scales: input.map(f64::abs),
+            input_labels: None,
+            output_labels: None,
+        }
+    }
+
+    pub fn evaluate(
+        function: ObjectiveFunction<VARIABLE_COUNT, OUTPUT_COUNT>,
+        x: [f64; VARIABLE_COUNT],
+    ) -> Self
+    {
+        let (values, jacobian) = evaluate(function, x);
+        Self::new(x, values, jacobian)
+    }
+
+    pub fn with_scales(mut self, scales: [f64; VARIABLE_COUNT]) -> Self
+    {
+        self.scales = scales;
+        self
+    }
+
+    pub fn with_input_labels(mut self, labels: [&'a str; VARIABLE_COUNT]) -> Self
+    {
+        self.input_labels = Some(labels);
+        self
+    }
+
+    pub fn with_output_labels(mut self, labels: [&'a str; OUTPUT_COUNT]) -> Self
+    {
+        self.output_labels = Some(labels);
+        self
+    }
+
+    pub fn elasticity(&self, output: usize, input: usize) -> Option<f64>
+    {
+        let value = self.values[output];
+        if value == 0.0 {
+            return None;
+        }
+        if self.input[input] == 0.0 {
+            return Some(0.0);
+        }
+        Some(self.jacobian[output][input] * self.input[input] / value)
+    }
+
+    pub fn elasticities(&self) -> [[Option<f64>; VARIABLE_COUNT]; OUTPUT_COUNT]
+    {
+        core::array::from_fn(|output| core::array::from_fn(|input| self.elasticity(output, input)))
+    }
+
+    pub fn normalised(&self, output: usize, input: usize) -> f64
+    {
+        if self.scales[input] == 0.0 {
+            return 0.0;
+        }
+        self.jacobian[output][input] * self.scales[input]
+    }
+
+    // Inputs ordered from the most to the least influential on an output, by the magnitude of the
+    // normalised sensitivity. Ties keep the input order.
+    pub fn ranking(&self, output: usize) -> [usize; VARIABLE_COUNT]
+    {
+        let mut ranking: [usize; VARIABLE_COUNT] = core::array::from_fn(|input| input);
+        ranking.sort_unstable_by(|&a, &b| {
+            let a_magnitude = self.normalised(output, a).abs();
+            let b_magnitude = self.normalised(output, b).abs();
+            b_magnitude.total_cmp(&a_magnitude).then(a.cmp(&b))
+        });
+        ranking
+    }
+
+    // Componentwise relative condition number Σ |J x| / |y| of an output, the sum of the
+    // magnitudes of its elasticities: how much relative errors in all inputs are amplified at
+    // most. None for outputs that are zero unless no input moves them.
+    pub fn condition_number(&self, output: usize) -> Option<f64>
+    {
+        let amplification: f64 = (0..VARIABLE_COUNT)
+            .map(|input| (self.jacobian[output][input] * self.input[input]).abs())
+            .sum();
+        let value = self.values[output].abs();
+        if value == 0.0 {
+            return if amplification == 0.0 {
+                Some(0.0)
+            } else {
+                None
+            };
+        }
+        Some(amplification / value)
+    }
+
+    pub fn condition_numbers(&self) -> [Option<f64>; OUTPUT_COUNT]
+    {
+        core::array::from_fn(|output| self.condition_number(output))
+    }
+
+    // Aligned table with one row per output and input, see SensitivityTable.
+    pub fn table(&self, order: Order) -> SensitivityTable<'_, 'a, VARIABLE_COUNT, OUTPUT_COUNT>
+    {
+        SensitivityTable {
+            sensitivity: self,
+            order,
+            csv: false,
+        }
+    }
+
+    // The same rows as the table as comma separated values with a header line, undefined
+    // elasticities and condition numbers are left empty.
+    pub fn csv(&self, order: Order) -> SensitivityTable<'_, 'a, VARIABLE_COUNT, OUTPUT_COUNT>
+    {
+        SensitivityTable {
+            sensitivity: self,
+            order,
+            csv: true,
+        }
+    }
+
+    fn input_label(&self, index: usize) -> Label<'a>
+    {
+        match self.input_labels {
+            Some(labels) => Label::Given(labels[index]),
+            None => Label::Indexed('x', index),
+        }
+    }
+
+    fn output_label(&self, index: usize) -> Label<'a>
+    {
+        match self.output_labels {
+            Some(labels) => Label::Given(labels[index]),
+            None => Label::Indexed('y', index),
+        }
+    }
+}
+
+// Order of the rows within each output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Order
+{
+    Input,
+    Influence,
+}
+
+enum Cell<'a>
+{
+    Label(Label<'a>),
+    Number(Number),
+    Empty,
+}
+
+impl Display for Cell<'_>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            Cell::Label(label) => label.fmt(f),
+            Cell::Number(number) => number.fmt(f),
+            Cell::Empty => Ok(()),
+        }
+    }
+}
+
+// A cell as a CSV field. Labels containing a comma, a quote or a line break are quoted per RFC
+// 4180, with the quotes inside doubled.
+struct Csv<'c, 'a>(&'c Cell<'a>);
+
+impl Display for Csv<'_, '_>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.0 {
+            Cell::Label(Label::Given(label)) if label.contains([',', '"', '\n', '\r']) => {
+                f.write_str("\"")?;
+                for (index, part) in label.split('"').enumerate() {
+                    if index > 0 {
+                        f.write_str("\"\"")?;
+                    }
+                    f.write_str(part)?;
+                }
+                f.write_str("\"")
+            }
+            cell => cell.fmt(f),
+        }
+    }
+}
+
+const HEADER: [&str; 7] = [
+    "output",
+    "input",
+    "rank",
+    "derivative",
+    "elasticity",
+    "normalised",
+    "condition",
+];
+
+// Renders a Sensitivity with one row per output and input, either aligned:
+//
+//     output  input  rank  derivative  elasticity  normalised  condition
+//     y0      x1        1       2.000       0.667       4.000      1.000
+//     y0      x0        2       1.000       0.333       2.000      1.000
+//
+// or as CSV. Undefined values are shown as - in the table. The precision of the format string
+// applies to every number.
+pub struct SensitivityTable<'s, 'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    sensitivity: &'s Sensitivity<'a, VARIABLE_COUNT, OUTPUT_COUNT>,
+    order: Order,
+    csv: bool,
+}
+
+impl<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+    SensitivityTable<'_, 'a, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    // Calls row for every (output, input) pair in display order.
+    fn for_each_row(
+        &self,
+        mut row: impl FnMut([Cell<'a>; 7]) -> fmt::Result,
+        precision: Option<usize>,
+    ) -> fmt::Result
+    {
+        let sensitivity = self.sensitivity;
+        let number = |value| Cell::Number(Number { value, precision });
+        let optional = |value: Option<f64>| value.map_or(Cell::Empty, number);
+
+        for output in 0..OUTPUT_COUNT {
+            let ranking = sensitivity.ranking(output);
+            let mut ranks = [0; VARIABLE_COUNT];
+            for (rank, &input) in ranking.iter().enumerate() {
+                ranks[input] = rank + 1;
+            }
+            let order: [usize; VARIABLE_COUNT] = match self.order {
+                Order::Input => core::array::from_fn(|input| input),
+                Order::Influence => ranking,
+            };
+
+            for input in order {
+                row([
+                    Cell::Label(sensitivity.output_label(output)),
+                    Cell::Label(sensitivity.input_label(input)),
+                    Cell::Number(Number {
+                        value: ranks[input] as f64,
+                        precision: Some(0),
+                    }),
+                    number(sensitivity.jacobian[output][input]),
+                    optional(sensitivity.elasticity(output, input)),
+                    number(sensitivity.normalised(output, input)),
+                    optional(sensitivity.condition_number(output)),
+                ])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize> Display
+    for SensitivityTable<'_, '_, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let precision = f.precision();
+
+        if self.csv {
+            for (index, title) in HEADER.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{}", title)?;
+            }
+            writeln!(f)?;
+            return self.for_each_row(
+                |cells| {
+                    for (index, cell) in cells.iter().enumerate() {
+                        if index > 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{}", Csv(cell))?;
+                    }
+                    writeln!(f)
+                },
+                precision,
+            );
+        }
+
+        let mut widths = HEADER.map(width);
+        self.for_each_row(
+            |cells| {
+                for (width, cell) in widths.iter_mut().zip(&cells) {
+                    *width = (*width).max(self::width(Undefined(cell)));
+                }
+                Ok(())
+            },
+            precision,
+        )?;
+
+        // Labels are left aligned, numbers right aligned.
+        let write_row = |f: &mut fmt::Formatter, cells: [&dyn Display; 7]| {
+            for (index, (cell, &width)) in cells.iter().zip(&widths).enumerate() {
+                if index < 2 {
+                    let padding = if index == 0 { 0 } else { SEPARATOR };
+                    write_padded(f, "", padding, true)?;
+                    write_padded(f, cell, width, true)?;
+                } else {
+                    write_padded(f, cell, width + SEPARATOR, false)?;
+                }
+            }
+            writeln!(f)
+        };
+
+        write_row(
+            f,
+            core::array::from_fn(|index| &HEADER[index] as &dyn Display),
+        )?;
+        self.for_each_row(
+            |cells| {
+                let cells = cells.each_ref().map(Undefined);
+                write_row(
+                    f,
+                    core::array::from_fn(|index| &cells[index] as &dyn Display),
+                )
+            },
+            precision,
+        )
+    }
+}
+
+// Shows empty cells as - in the aligned table.
+struct Undefined<'c, 'a>(&'c Cell<'a>);
+
+impl Display for Undefined<'_, '_>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.0 {
+            Cell::Empty => f.write_str("-"),
+            cell => cell.fmt(f),
+        }
+    }
+}
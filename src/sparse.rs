@@ -0,0 +1,410 @@
+use std::cell::RefCell;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::custom::{ChainRule, CustomFn};
+use super::evaluate::*;
+use super::math;
+use super::scalar::*;
+use super::variable::*;
+
+// The dependency sets of every intermediate value of the objective being traced, as sorted input
+// indices so that memory grows with the nonzeros and not with the number of inputs. A Dependency
+// only holds an index into this arena so that it stays Copy like the other scalars. Index 0 is
+// the empty set used for constants.
+thread_local! {
+    static DEPENDENCY_SETS: RefCell<Vec<Vec<usize>>> = const { RefCell::new(Vec::new()) };
+}
+
+// Merges two sorted index lists, returns None when the union is one of the two.
+fn merge(left: &[usize], right: &[usize]) -> Option<Vec<usize>>
+{
+    let mut union = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] < right[j] {
+            union.push(left[i]);
+            i += 1;
+        } else if right[j] < left[i] {
+            union.push(right[j]);
+            j += 1;
+        } else {
+            union.push(left[i]);
+            i += 1;
+            j += 1;
+        }
+    }
+    union.extend_from_slice(&left[i..]);
+    union.extend_from_slice(&right[j..]);
+    if union.len() == left.len() || union.len() == right.len() {
+        None
+    } else {
+        Some(union)
+    }
+}
+
+// Scalar which tracks the set of inputs a value depends on. The value itself is carried along so
+// that objectives which branch on values trace the branch taken at the given point.
+#[derive(Debug, Copy, Clone)]
+pub struct Dependency
+{
+    pub value: f64,
+    set: usize,
+}
+
+impl Dependency
+{
+    fn unary(&self, value: f64) -> Self
+    {
+        Self {
+            value,
+            set: self.set,
+        }
+    }
+
+    fn binary(&self, other: &Self, value: f64) -> Self
+    {
+        let set = if self.set == other.set || other.set == 0 {
+            self.set
+        } else if self.set == 0 {
+            other.set
+        } else {
+            DEPENDENCY_SETS.with(|sets| {
+                let mut sets = sets.borrow_mut();
+                match merge(&sets[self.set], &sets[other.set]) {
+                    Some(union) => {
+                        sets.push(union);
+                        sets.len() - 1
+                    }
+                    // One set contains the other, share it instead of storing a copy.
+                    None if sets[self.set].len() >= sets[other.set].len() => self.set,
+                    None => other.set,
+                }
+            })
+        };
+        Self { value, set }
+    }
+}
+
+impl Add for Dependency
+{
+    type Output = Dependency;
+
+    fn add(self, other: Dependency) -> Dependency
+    {
+        self.binary(&other, self.value + other.value)
+    }
+}
+
+impl Sub for Dependency
+{
+    type Output = Dependency;
+
+    fn sub(self, other: Dependency) -> Dependency
+    {
+        self.binary(&other, self.value - other.value)
+    }
+}
+
+impl Mul for Dependency
+{
+    type Output = Dependency;
+
+    fn mul(self, other: Dependency) -> Dependency
+    {
+        self.binary(&other, self.value * other.value)
+    }
+}
+
+impl Div for Dependency
+{
+    type Output = Dependency;
+
+    fn div(self, other: Dependency) -> Dependency
+    {
+        self.binary(&other, self.value / other.value)
+    }
+}
+
+impl Neg for Dependency
+{
+    type Output = Dependency;
+
+    fn neg(self) -> Dependency
+    {
+        self.unary(-self.value)
+    }
+}
+
+impl ChainRule for Dependency
+{
+    fn apply_custom<F: CustomFn + Clone + Send + Sync + 'static>(self, function: &F) -> Self
+    {
+        self.unary(function.value(self.value))
+    }
+}
+
+impl Scalar for Dependency
+{
+    fn constant(value: f64) -> Self
+    {
+        Self { value, set: 0 }
+    }
+
+    fn sqrt(self) -> Self
+    {
+        self.unary(math::sqrt(self.value))
+    }
+
+    fn sin(self) -> Self
+    {
+        self.unary(math::sin(self.value))
+    }
+
+    fn cos(self) -> Self
+    {
+        self.unary(math::cos(self.value))
+    }
+
+    fn tan(self) -> Self
+    {
+        self.unary(math::tan(self.value))
+    }
+
+    fn ln(self) -> Self
+    {
+        self.unary(math::ln(self.value))
+    }
+
+    fn exp(self) -> Self
+    {
+        self.unary(math::exp(self.value))
+    }
+
+    fn powf(self, exponent: f64) -> Self
+    {
+        self.unary(math::powf(self.value, exponent))
+    }
+}
+
+impl Real for Dependency
+{
+    fn value(&self) -> f64
+    {
+        self.value
+    }
+}
+
+// For every output, the sorted indices of the inputs it depends on.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparsityPattern
+{
+    pub rows: Vec<Vec<usize>>,
+    pub column_count: usize,
+}
+
+impl SparsityPattern
+{
+    pub fn non_zero_count(&self) -> usize
+    {
+        self.rows.iter().map(|row| row.len()).sum()
+    }
+}
+
+pub fn detect_sparsity<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+) -> SparsityPattern
+{
+    DEPENDENCY_SETS.with(|sets| {
+        let mut sets = sets.borrow_mut();
+        sets.clear();
+        sets.push(Vec::new());
+        sets.extend((0..VARIABLE_COUNT).map(|input_index| vec![input_index]));
+    });
+
+    let mut variables = [Dependency::constant(0.0); VARIABLE_COUNT];
+    for (input_index, (variable, value)) in variables.iter_mut().zip(x).enumerate() {
+        *variable = Dependency {
+            value,
+            set: input_index + 1,
+        };
+    }
+    let outputs = objective.call(variables);
+
+    DEPENDENCY_SETS.with(|sets| {
+        let mut sets = sets.borrow_mut();
+        let rows = outputs
+            .iter()
+            .map(|output| sets[output.set].clone())
+            .collect();
+        sets.clear();
+        SparsityPattern {
+            rows,
+            column_count: VARIABLE_COUNT,
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coloring
+{
+    pub colors: Vec<usize>,
+    pub color_count: usize,
+}
+
+// Greedy distance-2 coloring, columns which share an output never get the same color. Columns of
+// one color can then be seeded together since their derivatives never end up in the same output.
+pub fn color_columns(pattern: &SparsityPattern) -> Coloring
+{
+    let mut columns = vec![Vec::new(); pattern.column_count];
+    for (row_index, row) in pattern.rows.iter().enumerate() {
+        for &column in row {
+            columns[column].push(row_index);
+        }
+    }
+
+    let mut colors = vec![usize::MAX; pattern.column_count];
+    let mut color_count = 0;
+    let mut forbidden = Vec::new();
+    for column in 0..pattern.column_count {
+        forbidden.clear();
+        for &row_index in &columns[column] {
+            for &neighbour in &pattern.rows[row_index] {
+                if colors[neighbour] != usize::MAX {
+                    forbidden.push(colors[neighbour]);
+                }
+            }
+        }
+        let color = (0..).find(|color| !forbidden.contains(color)).unwrap();
+        colors[column] = color;
+        color_count = color_count.max(color + 1);
+    }
+
+    Coloring {
+        colors,
+        color_count,
+    }
+}
+
+// Jacobian in coordinate format, entries are ordered by row and then by column.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparseJacobian
+{
+    pub row_count: usize,
+    pub column_count: usize,
+    pub row_indices: Vec<usize>,
+    pub column_indices: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsrMatrix
+{
+    pub row_count: usize,
+    pub column_count: usize,
+    pub row_offsets: Vec<usize>,
+    pub column_indices: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl SparseJacobian
+{
+    pub fn get(&self, row: usize, column: usize) -> f64
+    {
+        // Binary search for the row, then for the column within it.
+        let start = self.row_indices.partition_point(|&r| r < row);
+        let end = start + self.row_indices[start..].partition_point(|&r| r == row);
+        self.column_indices[start..end]
+            .binary_search(&column)
+            .map_or(0.0, |index| self.values[start + index])
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix
+    {
+        let mut row_offsets = vec![0; self.row_count + 1];
+        for &row in &self.row_indices {
+            row_offsets[row + 1] += 1;
+        }
+        for row in 0..self.row_count {
+            row_offsets[row + 1] += row_offsets[row];
+        }
+        CsrMatrix {
+            row_count: self.row_count,
+            column_count: self.column_count,
+            row_offsets,
+            column_indices: self.column_indices.clone(),
+            values: self.values.clone(),
+        }
+    }
+
+    pub fn to_dense(&self) -> Vec<Vec<f64>>
+    {
+        let mut dense = vec![vec![0.0; self.column_count]; self.row_count];
+        for ((&row, &column), &value) in self
+            .row_indices
+            .iter()
+            .zip(&self.column_indices)
+            .zip(&self.values)
+        {
+            dense[row][column] = value;
+        }
+        dense
+    }
+}
+
+// Computes the jacobian with one pass per color instead of one pass per input.
+pub fn evaluate_sparse<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>(
+    objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+    x: [f64; VARIABLE_COUNT],
+    pattern: &SparsityPattern,
+    coloring: &Coloring,
+) -> ([f64; OUTPUT_COUNT], SparseJacobian)
+{
+    assert_eq!(pattern.rows.len(), OUTPUT_COUNT);
+    assert_eq!(pattern.column_count, VARIABLE_COUNT);
+
+    let values = objective.call(x);
+    let mut derivatives = vec![0.0; pattern.non_zero_count()];
+
+    for color in 0..coloring.color_count {
+        let mut variables = x.map(Variable::constant);
+        for (variable, &column_color) in variables.iter_mut().zip(&coloring.colors) {
+            if column_color == color {
+                variable.derivative = 1.0;
+            }
+        }
+        let outputs = objective.call(variables);
+
+        let mut entry = 0;
+        for (output, row) in outputs.iter().zip(&pattern.rows) {
+            for &column in row {
+                if coloring.colors[column] == color {
+                    derivatives[entry] = output.derivative;
+                }
+                entry += 1;
+            }
+        }
+    }
+
+    let mut row_indices = Vec::with_capacity(derivatives.len());
+    let mut column_indices = Vec::with_capacity(derivatives.len());
+    for (row_index, row) in pattern.rows.iter().enumerate() {
+        for &column in row {
+            row_indices.push(row_index);
+            column_indices.push(column);
+        }
+    }
+
+    let jacobian = SparseJacobian {
+        row_count: OUTPUT_COUNT,
+        column_count: VARIABLE_COUNT,
+        row_indices,
+        column_indices,
+        values: derivatives,
+    };
+    (values, jacobian)
+}
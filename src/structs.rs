@@ -0,0 +1,210 @@
+use std::ops::Index;
+
+use super::variable::*;
+
+// Scalars a Differentiable struct can be made of.
+pub trait Element: Copy + Differentiable<Scalar = Self>
+{
+    fn zero() -> Self;
+}
+
+// Types which flatten to a fixed number of scalars, in declaration order. Implemented for f64,
+// Variable, arrays of Differentiable types, and with #[derive(Differentiable)] from
+// autocrab-macros for structs generic over their scalar type:
+//
+//     #[derive(Differentiable)]
+//     struct Pose<T> { x: T, y: T, theta: T }
+//
+// With<U> is the same shape over another scalar, Pose<f64>::With<Variable> is Pose<Variable>.
+pub trait Differentiable: Sized
+{
+    type Scalar: Element;
+    type With<U: Element>: Differentiable<Scalar = U>;
+
+    const SIZE: usize;
+
+    // Both take exactly SIZE values.
+    fn write_values(&self, values: &mut [Self::Scalar]);
+    fn read_values(values: &[Self::Scalar]) -> Self;
+
+    // Appends the name of every scalar, fields joined with dots and array elements with [i], e.g.
+    // pose.x or wheels[2].
+    fn field_names(prefix: &str, names: &mut Vec<String>);
+
+    fn to_array<const N: usize>(&self) -> [Self::Scalar; N]
+    {
+        assert_eq!(N, Self::SIZE);
+        let mut values = [Self::Scalar::zero(); N];
+        self.write_values(&mut values);
+        values
+    }
+
+    fn from_array<const N: usize>(values: [Self::Scalar; N]) -> Self
+    {
+        assert_eq!(N, Self::SIZE);
+        Self::read_values(&values)
+    }
+
+    fn names() -> Vec<String>
+    {
+        let mut names = Vec::with_capacity(Self::SIZE);
+        Self::field_names("", &mut names);
+        names
+    }
+}
+
+// Name of a field below prefix, used by the derive.
+pub fn field_name(prefix: &str, field: &str) -> String
+{
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", prefix, field)
+    }
+}
+
+macro_rules! element {
+    ($type:ty, $zero:expr) => {
+        impl Element for $type
+        {
+            fn zero() -> Self
+            {
+                $zero
+            }
+        }
+
+        impl Differentiable for $type
+        {
+            type Scalar = $type;
+            type With<U: Element> = U;
+
+            const SIZE: usize = 1;
+
+            fn write_values(&self, values: &mut [Self::Scalar])
+            {
+                values[0] = *self;
+            }
+
+            fn read_values(values: &[Self::Scalar]) -> Self
+            {
+                values[0]
+            }
+
+            fn field_names(prefix: &str, names: &mut Vec<String>)
+            {
+                names.push(prefix.to_string());
+            }
+        }
+    };
+}
+
+element!(f64, 0.0);
+element!(Variable, Variable::empty());
+
+impl<D: Differentiable, const N: usize> Differentiable for [D; N]
+{
+    type Scalar = D::Scalar;
+    type With<U: Element> = [D::With<U>; N];
+
+    const SIZE: usize = D::SIZE * N;
+
+    fn write_values(&self, values: &mut [Self::Scalar])
+    {
+        for (element, values) in self.iter().zip(values.chunks_exact_mut(D::SIZE.max(1))) {
+            element.write_values(values);
+        }
+    }
+
+    fn read_values(values: &[Self::Scalar]) -> Self
+    {
+        std::array::from_fn(|i| D::read_values(&values[i * D::SIZE..(i + 1) * D::SIZE]))
+    }
+
+    fn field_names(prefix: &str, names: &mut Vec<String>)
+    {
+        for i in 0..N {
+            D::field_names(&format!("{}[{}]", prefix, i), names);
+        }
+    }
+}
+
+// Jacobian of an objective over structs, one input shaped row per scalar of the output:
+// `jacobian["heading"].theta` is the derivative of the output field heading with respect to the
+// input field theta.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructJacobian<I>
+{
+    pub output_names: Vec<String>,
+    pub rows: Vec<I>,
+}
+
+impl<I> StructJacobian<I>
+{
+    pub fn row(&self, output: &str) -> Option<&I>
+    {
+        let index = self.output_names.iter().position(|name| name == output)?;
+        Some(&self.rows[index])
+    }
+}
+
+impl<I> Index<&str> for StructJacobian<I>
+{
+    type Output = I;
+
+    fn index(&self, output: &str) -> &I
+    {
+        match self.row(output) {
+            Some(row) => row,
+            None => panic!("unknown output {:?}", output),
+        }
+    }
+}
+
+// Evaluates an objective taking and returning Differentiable types, seeding one scalar of the
+// input per pass like evaluate does for arrays.
+pub fn evaluate_struct<I, O>(
+    function: impl Fn(I::With<Variable>) -> O,
+    x: &I,
+) -> (O::With<f64>, StructJacobian<I>)
+where
+    I: Differentiable<Scalar = f64>,
+    O: Differentiable<Scalar = Variable>,
+{
+    let mut input = vec![0.0; I::SIZE];
+    x.write_values(&mut input);
+
+    let pass = |seed: Option<usize>| {
+        let variables: Vec<Variable> = input
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| {
+                let derivative = if seed == Some(index) { 1.0 } else { 0.0 };
+                Variable::with_derivative(value, derivative)
+            })
+            .collect();
+        let mut outputs = vec![Variable::empty(); O::SIZE];
+        function(I::With::<Variable>::read_values(&variables)).write_values(&mut outputs);
+        outputs
+    };
+
+    let columns: Vec<Vec<Variable>> = (0..I::SIZE).map(|index| pass(Some(index))).collect();
+    // One pass for the values even without inputs.
+    let values: Vec<f64> = match columns.first() {
+        Some(column) => column.iter().map(|output| output.value).collect(),
+        None => pass(None).iter().map(|output| output.value).collect(),
+    };
+    let jacobian: Vec<Vec<f64>> = (0..O::SIZE)
+        .map(|output_index| {
+            columns
+                .iter()
+                .map(|column| column[output_index].derivative)
+                .collect()
+        })
+        .collect();
+
+    let struct_jacobian = StructJacobian {
+        output_names: O::names(),
+        rows: jacobian.iter().map(|row| I::read_values(row)).collect(),
+    };
+    (O::With::<f64>::read_values(&values), struct_jacobian)
+}
@@ -0,0 +1,208 @@
+use core::fmt::{self, Display, Write};
+
+// Counts the characters a value would print as, so columns can be aligned without allocating.
+struct Counter(usize);
+
+impl Write for Counter
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result
+    {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+pub(crate) fn width(item: impl Display) -> usize
+{
+    let mut counter = Counter(0);
+    let _ = write!(counter, "{}", item);
+    counter.0
+}
+
+pub(crate) fn write_padded(
+    f: &mut fmt::Formatter,
+    item: impl Display,
+    width: usize,
+    left_aligned: bool,
+) -> fmt::Result
+{
+    let padding = width.saturating_sub(self::width(&item));
+    if left_aligned {
+        write!(f, "{}", item)?;
+    }
+    for _ in 0..padding {
+        f.write_char(' ')?;
+    }
+    if !left_aligned {
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+pub(crate) struct Number
+{
+    pub value: f64,
+    pub precision: Option<usize>,
+}
+
+impl Display for Number
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.precision {
+            Some(precision) => write!(f, "{:.*}", precision, self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+pub(crate) enum Label<'a>
+{
+    Given(&'a str),
+    Indexed(char, usize),
+}
+
+impl Display for Label<'_>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            Label::Given(label) => f.write_str(label),
+            Label::Indexed(prefix, index) => write!(f, "{}{}", prefix, index),
+        }
+    }
+}
+
+pub(crate) const SEPARATOR: usize = 2;
+
+// Renders a jacobian, optionally with the values, as an aligned table with one row per output and
+// one column per input:
+//
+//         value     x0     x1
+//     y0  6.000  3.000  2.000
+//     y1  2.841  0.540  1.000
+//
+// Inputs are labelled x0, x1, ... and outputs y0, y1, ... unless labels are given. The precision
+// of the format string applies to every number, `{:.3}`.
+#[derive(Debug, Copy, Clone)]
+pub struct JacobianTable<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    values: Option<&'a [f64; OUTPUT_COUNT]>,
+    jacobian: &'a [[f64; VARIABLE_COUNT]; OUTPUT_COUNT],
+    input_labels: Option<[&'a str; VARIABLE_COUNT]>,
+    output_labels: Option<[&'a str; OUTPUT_COUNT]>,
+}
+
+impl<'a, const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+    JacobianTable<'a, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    pub fn new(jacobian: &'a [[f64; VARIABLE_COUNT]; OUTPUT_COUNT]) -> Self
+    {
+        Self {
+            values: None,
+            jacobian,
+            input_labels: None,
+            output_labels: None,
+        }
+    }
+
+    // Table for the result of evaluate, with a value column in front of the derivatives.
+    pub fn evaluation(
+        result: &'a ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT]),
+    ) -> Self
+    {
+        Self::new(&result.1).with_values(&result.0)
+    }
+
+    pub fn with_values(mut self, values: &'a [f64; OUTPUT_COUNT]) -> Self
+    {
+        self.values = Some(values);
+        self
+    }
+
+    pub fn with_input_labels(mut self, labels: [&'a str; VARIABLE_COUNT]) -> Self
+    {
+        self.input_labels = Some(labels);
+        self
+    }
+
+    pub fn with_output_labels(mut self, labels: [&'a str; OUTPUT_COUNT]) -> Self
+    {
+        self.output_labels = Some(labels);
+        self
+    }
+
+    fn input_label(&self, index: usize) -> Label<'a>
+    {
+        match self.input_labels {
+            Some(labels) => Label::Given(labels[index]),
+            None => Label::Indexed('x', index),
+        }
+    }
+
+    fn output_label(&self, index: usize) -> Label<'a>
+    {
+        match self.output_labels {
+            Some(labels) => Label::Given(labels[index]),
+            None => Label::Indexed('y', index),
+        }
+    }
+}
+
+impl<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize> Display
+    for JacobianTable<'_, VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let precision = f.precision();
+        let number = |value| Number { value, precision };
+
+        let label_width = (0..OUTPUT_COUNT)
+            .map(|row| width(self.output_label(row)))
+            .max()
+            .unwrap_or(0);
+        let value_width = self.values.map(|values| {
+            values
+                .iter()
+                .map(|&value| width(number(value)))
+                .fold(width("value"), usize::max)
+        });
+        let column_width = |column: usize| {
+            self.jacobian
+                .iter()
+                .map(|row| width(number(row[column])))
+                .fold(width(self.input_label(column)), usize::max)
+        };
+
+        write_padded(f, "", label_width, true)?;
+        if let Some(value_width) = value_width {
+            write_padded(f, "value", value_width + SEPARATOR, false)?;
+        }
+        for column in 0..VARIABLE_COUNT {
+            write_padded(
+                f,
+                self.input_label(column),
+                column_width(column) + SEPARATOR,
+                false,
+            )?;
+        }
+        writeln!(f)?;
+
+        for (row, derivatives) in self.jacobian.iter().enumerate() {
+            write_padded(f, self.output_label(row), label_width, true)?;
+            if let (Some(values), Some(value_width)) = (self.values, value_width) {
+                write_padded(f, number(values[row]), value_width + SEPARATOR, false)?;
+            }
+            for (column, &derivative) in derivatives.iter().enumerate() {
+                write_padded(
+                    f,
+                    number(derivative),
+                    column_width(column) + SEPARATOR,
+                    false,
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use super::custom::*;
+use super::evaluate::*;
+use super::expression::*;
+use super::functions;
+use super::variable::*;
+
+// One instruction per graph node, the result of instruction i is stored in register i.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Instruction
+{
+    Input(usize),
+    Constant(f64),
+    Unary(UnaryOperation, usize),
+    Binary(BinaryOperation, usize, usize),
+    Powf(usize, f64),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(usize, usize),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CustomDerivative(usize, usize),
+}
+
+// Flat, immutable instruction list compiled from a traced objective. It holds no interior
+// mutability, so one tape can be shared between threads, each with its own Workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tape<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize>
+{
+    instructions: Vec<Instruction>,
+    outputs: [usize; OUTPUT_COUNT],
+    functions: Vec<SharedFn>,
+}
+
+// Registers for replaying a tape, allocate once and reuse for every call.
+#[derive(Debug, Clone)]
+pub struct Workspace
+{
+    registers: Vec<Variable>,
+}
+
+fn apply_unary(operation: UnaryOperation, a: Variable) -> Variable
+{
+    match operation {
+        UnaryOperation::Neg => -a,
+        UnaryOperation::Sqrt => functions::sqrt(a),
+        UnaryOperation::Sin => functions::sin(a),
+        UnaryOperation::Cos => functions::cos(a),
+        UnaryOperation::Tan => functions::tan(a),
+        UnaryOperation::Ln => functions::ln(a),
+        UnaryOperation::Exp => functions::exp(a),
+    }
+}
+
+fn apply_binary(operation: BinaryOperation, a: Variable, b: Variable) -> Variable
+{
+    match operation {
+        BinaryOperation::Add => a + b,
+        BinaryOperation::Sub => a - b,
+        BinaryOperation::Mul => a * b,
+        BinaryOperation::Div => a / b,
+    }
+}
+
+impl<const VARIABLE_COUNT: usize, const OUTPUT_COUNT: usize> Tape<VARIABLE_COUNT, OUTPUT_COUNT>
+{
+    pub fn compile(graph: &ExpressionGraph) -> Self
+    {
+        assert_eq!(graph.input_count, VARIABLE_COUNT);
+        assert_eq!(graph.outputs.len(), OUTPUT_COUNT);
+
+        let instructions = graph
+            .nodes
+            .iter()
+            .map(|node| match *node {
+                Node::Input(index) => Instruction::Input(index),
+                Node::Constant(value) => Instruction::Constant(value),
+                Node::Unary(operation, a) => Instruction::Unary(operation, a),
+                Node::Binary(operation, a, b) => Instruction::Binary(operation, a, b),
+                Node::Powf(a, exponent) => Instruction::Powf(a, exponent),
+                Node::Custom(function, a) => Instruction::Custom(function, a),
+                Node::CustomDerivative(function, a) => Instruction::CustomDerivative(function, a),
+            })
+            .collect();
+
+        let mut outputs = [0; OUTPUT_COUNT];
+        outputs.copy_from_slice(&graph.outputs);
+        Self {
+            instructions,
+            outputs,
+            functions: graph.functions.clone(),
+        }
+    }
+
+    // Traces the objective at x and compiles the simplified graph. Branches taken at x are baked
+    // into the tape.
+    pub fn record(
+        objective: &impl Objective<VARIABLE_COUNT, OUTPUT_COUNT>,
+        x: [f64; VARIABLE_COUNT],
+    ) -> Self
+    {
+        Self::compile(&trace(objective, x).simplify())
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.instructions.is_empty()
+    }
+
+    pub fn workspace(&self) -> Workspace
+    {
+        Workspace {
+            registers: vec![Variable::empty(); self.instructions.len()],
+        }
+    }
+
+    fn replay(&self, variables: &[Variable; VARIABLE_COUNT], workspace: &mut Workspace)
+    {
+        assert_eq!(workspace.registers.len(), self.instructions.len());
+        let registers = &mut workspace.registers;
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            registers[index] = match *instruction {
+                Instruction::Input(input) => variables[input],
+                Instruction::Constant(value) => Variable::with_derivative(value, 0.0),
+                Instruction::Unary(operation, a) => apply_unary(operation, registers[a]),
+                Instruction::Binary(operation, a, b) => {
+                    apply_binary(operation, registers[a], registers[b])
+                }
+                Instruction::Powf(a, exponent) => functions::pow(registers[a], exponent),
+                Instruction::Custom(function, a) => {
+                    registers[a].apply_custom(&self.functions[function])
+                }
+                // Custom functions only come with their first derivative.
+                Instruction::CustomDerivative(function, a) => {
+                    let a = registers[a];
+                    Variable::with_derivative(
+                        self.functions[function].derivative(a.value),
+                        chain(a.derivative, f64::NAN),
+                    )
+                }
+            };
+        }
+    }
+
+    pub fn values(&self, x: [f64; VARIABLE_COUNT], workspace: &mut Workspace)
+        -> [f64; OUTPUT_COUNT]
+    {
+        let variables = x.map(|value| Variable::with_derivative(value, 0.0));
+        self.replay(&variables, workspace);
+        self.outputs.map(|output| workspace.registers[output].value)
+    }
+
+    // Same result as evaluate, with one replay per input and no allocation.
+    pub fn evaluate(
+        &self,
+        x: [f64; VARIABLE_COUNT],
+        workspace: &mut Workspace,
+    ) -> ([f64; OUTPUT_COUNT], [[f64; VARIABLE_COUNT]; OUTPUT_COUNT])
+    {
+        let mut values = [0.0; OUTPUT_COUNT];
+        let mut jacobian = [[0.0; VARIABLE_COUNT]; OUTPUT_COUNT];
+
+        for input_index in 0..VARIABLE_COUNT {
+            let mut variables = x.map(|value| Variable::with_derivative(value, 0.0));
+            variables[input_index].derivative = 1.0;
+            self.replay(&variables, workspace);
+            for (output_index, &output) in self.outputs.iter().enumerate() {
+                values[output_index] = workspace.registers[output].value;
+                jacobian[output_index][input_index] = workspace.registers[output].derivative;
+            }
+        }
+        (values, jacobian)
+    }
+}
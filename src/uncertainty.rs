@@ -0,0 +1,142 @@
+use super::evaluate::*;
+use super::linalg::*;
+use super::math;
+use super::variable::*;
+
+// Means and covariance of a set of measured quantities.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Measurement<const N: usize>
+{
+    pub mean: Vector<N>,
+    pub covariance: Matrix<N, N>,
+}
+
+impl<const N: usize> Measurement<N>
+{
+    pub fn new(mean: [f64; N], covariance: [[f64; N]; N]) -> Self
+    {
+        Self {
+            mean: Vector(mean),
+            covariance: Matrix(covariance),
+        }
+    }
+
+    // Uncorrelated inputs, the covariance is diagonal with the squared standard deviations.
+    pub fn independent(mean: [f64; N], standard_deviations: [f64; N]) -> Self
+    {
+        let mut covariance = Matrix::zeros();
+        for (i, deviation) in standard_deviations.into_iter().enumerate() {
+            covariance.0[i][i] = deviation * deviation;
+        }
+        Self {
+            mean: Vector(mean),
+            covariance,
+        }
+    }
+
+    pub fn standard_deviations(&self) -> [f64; N]
+    {
+        core::array::from_fn(|i| math::sqrt(self.covariance.0[i][i]))
+    }
+
+    pub fn correlation(&self, a: usize, b: usize) -> f64
+    {
+        let deviations = self.standard_deviations();
+        self.covariance.0[a][b] / (deviations[a] * deviations[b])
+    }
+
+    // First order propagation: the outputs are f at the mean with covariance J Σ Jᵀ, J being the
+    // jacobian at the mean. Exact for linear functions, otherwise only as good as the
+    // linearisation over the spread of the inputs.
+    pub fn propagate<const M: usize>(&self, function: ObjectiveFunction<N, M>) -> Measurement<M>
+    {
+        let (values, jacobian) = evaluate_matrix(function, self.mean);
+        Measurement {
+            mean: values,
+            covariance: jacobian * self.covariance * jacobian.transpose(),
+        }
+    }
+
+    // Sample statistics of f over samples drawn from a normal distribution with this mean and
+    // covariance, for validating propagate. The same seed always gives the same result. None if
+    // the covariance is not positive semidefinite.
+    pub fn monte_carlo<const M: usize>(
+        &self,
+        function: ObjectiveFunction<N, M>,
+        samples: usize,
+        seed: u64,
+    ) -> Option<Measurement<M>>
+    {
+        assert!(samples > 1);
+        let lower = self.covariance.cholesky()?;
+        let mut random = Random::new(seed);
+
+        // Welford's online algorithm, the running comoment divided by samples - 1 is the
+        // covariance.
+        let mut mean = Vector::<M>::zeros();
+        let mut comoment = Matrix::<M, M>::zeros();
+        for count in 1..=samples {
+            let normal = Vector(core::array::from_fn(|_| random.normal()));
+            let x = self.mean + lower * normal;
+            let y = Vector(
+                function(x.0.map(|value| Variable::with_derivative(value, 0.0)))
+                    .map(|output| output.value),
+            );
+
+            let delta = y - mean;
+            mean = mean + delta.scale(1.0 / count as f64);
+            let updated_delta = y - mean;
+            for i in 0..M {
+                for j in 0..M {
+                    comoment.0[i][j] += delta[i] * updated_delta[j];
+                }
+            }
+        }
+
+        let mut covariance = comoment;
+        for row in covariance.0.iter_mut() {
+            for entry in row.iter_mut() {
+                *entry /= (samples - 1) as f64;
+            }
+        }
+        Some(Measurement { mean, covariance })
+    }
+}
+
+// SplitMix64, small and good enough for sampling. Kept local so results do not depend on an
+// external generator.
+struct Random
+{
+    state: u64,
+}
+
+impl Random
+{
+    fn new(seed: u64) -> Self
+    {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64
+    {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in (0, 1], never 0 so the logarithm below is finite.
+    fn uniform(&mut self) -> f64
+    {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    // Box-Muller, one of the pair is discarded to keep the state simple.
+    fn normal(&mut self) -> f64
+    {
+        let radius = math::sqrt(-2.0 * math::ln(self.uniform()));
+        let angle = 2.0 * core::f64::consts::PI * self.uniform();
+        radius * math::cos(angle)
+    }
+}
@@ -1,10 +1,16 @@
-use std::ops::{Add, Div, Mul, Sub};
+use core::fmt;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use crate::math;
+
+// Dual number of a value and its derivative. T is f64 except for enclosures over intervals, see
+// interval::Interval.
 #[derive(Debug, Copy, Clone)]
-pub struct Variable
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variable<T = f64>
 {
-    pub value: f64,
-    pub derivative: f64,
+    pub value: T,
+    pub derivative: T,
 }
 
 impl Variable
@@ -32,9 +38,67 @@ impl Variable
 
     pub fn pow(&self, exponent: f64) -> Self
     {
-        Self {
-            value: self.value.powf(exponent),
-            derivative: exponent * self.derivative * self.value.powf(exponent - 1.0),
+        let (value, derivative) = power(self.value, self.derivative, exponent);
+        Self { value, derivative }
+    }
+}
+
+// Value and derivative of x^exponent for a dual number (value, derivative), shared by the scalar
+// types. Integer exponents use repeated multiplication, which is exact for small powers and defined
+// for negative bases. x^0 is the constant 1 and x^1 is x, also where x^-1 or x^0 is not finite.
+// A zero incoming derivative stays zero even where the derivative of the power itself is
+// infinite, e.g. x^0.5 at 0.
+pub(crate) fn power(value: f64, derivative: f64, exponent: f64) -> (f64, f64)
+{
+    if exponent == 0.0 {
+        return (1.0, 0.0);
+    }
+    if exponent == 1.0 {
+        return (value, derivative);
+    }
+
+    let is_integer = exponent % 1.0 == 0.0 && exponent.abs() <= i32::MAX as f64;
+    let (power, slope) = if is_integer {
+        let exponent = exponent as i32;
+        (math::powi(value, exponent), math::powi(value, exponent - 1))
+    } else {
+        (
+            math::powf(value, exponent),
+            math::powf(value, exponent - 1.0),
+        )
+    };
+    (power, chain(derivative, exponent * slope))
+}
+
+// Chain rule product which treats a zero incoming derivative as exactly zero, so an infinite or
+// undefined local derivative does not turn a constant into NaN.
+pub fn chain(derivative: f64, local_derivative: f64) -> f64
+{
+    if derivative == 0.0 {
+        0.0
+    } else {
+        derivative * local_derivative
+    }
+}
+
+// Dual number notation, `1.5 + 0.25·ε`. A precision applies to both parts.
+impl fmt::Display for Variable
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let sign = if self.derivative.is_sign_negative() {
+            '-'
+        } else {
+            '+'
+        };
+        let derivative = self.derivative.abs();
+        match f.precision() {
+            Some(precision) => write!(
+                f,
+                "{:.*} {} {:.*}·ε",
+                precision, self.value, sign, precision, derivative
+            ),
+            None => write!(f, "{} {} {}·ε", self.value, sign, derivative),
         }
     }
 }
@@ -191,7 +255,87 @@ impl Div<Variable> for f64
     {
         Variable {
             value: self / variable.value,
-            derivative: -self / (variable.value * variable.value),
+            derivative: chain(
+                variable.derivative,
+                -self / (variable.value * variable.value),
+            ),
         }
     }
 }
+
+impl Neg for Variable
+{
+    type Output = Variable;
+
+    fn neg(self) -> Variable
+    {
+        Variable {
+            value: -self.value,
+            derivative: -self.derivative,
+        }
+    }
+}
+
+impl AddAssign<Variable> for Variable
+{
+    fn add_assign(&mut self, other: Variable)
+    {
+        *self = *self + other;
+    }
+}
+
+impl AddAssign<f64> for Variable
+{
+    fn add_assign(&mut self, other: f64)
+    {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign<Variable> for Variable
+{
+    fn sub_assign(&mut self, other: Variable)
+    {
+        *self = *self - other;
+    }
+}
+
+impl SubAssign<f64> for Variable
+{
+    fn sub_assign(&mut self, other: f64)
+    {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<Variable> for Variable
+{
+    fn mul_assign(&mut self, other: Variable)
+    {
+        *self = *self * other;
+    }
+}
+
+impl MulAssign<f64> for Variable
+{
+    fn mul_assign(&mut self, other: f64)
+    {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign<Variable> for Variable
+{
+    fn div_assign(&mut self, other: Variable)
+    {
+        *self = *self / other;
+    }
+}
+
+impl DivAssign<f64> for Variable
+{
+    fn div_assign(&mut self, other: f64)
+    {
+        *self = *self / other;
+    }
+}
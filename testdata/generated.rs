@@ -0,0 +1,45 @@
+// Generated by autocrab, do not edit.
+
+pub fn hard(x: [f64; 2]) -> ([f64; 3], [[f64; 2]; 3])
+{
+    let v2 = x[0].sin();
+    let v3 = x[1] * x[1];
+    let v4 = v2 + v3;
+    let v5 = x[1].exp();
+    let v6 = x[0] * v5;
+    let v7 = x[1].sqrt();
+    let v8 = v6 / v7;
+    let v9 = x[0] * x[1];
+    let v10 = v9.cos();
+    let v11 = v10.ln();
+    let v12 = x[0].tan();
+    let v13 = v12.powf(1.5);
+    let v14 = v11 - v13;
+    let v16 = x[0].cos();
+    let v17 = x[1] + x[1];
+    let v19 = 2.0 * v7;
+    let v20 = v5 / v7;
+    let v21 = v8 / v7;
+    let v22 = 1.0 / v19;
+    let v23 = v21 * v22;
+    let v24 = v8 - v23;
+    let v25 = v9.sin();
+    let v26 = -v25;
+    let v27 = x[1] * v26;
+    let v28 = v27 / v10;
+    let v29 = v16.powf(2.0);
+    let v30 = 1.0 / v29;
+    let v31 = v12.powf(0.5);
+    let v33 = 1.5 * v31;
+    let v34 = v30 * v33;
+    let v35 = v28 - v34;
+    let v36 = x[0] * v26;
+    let v37 = v36 / v10;
+    ([v4, v8, v14], [[v16, v17], [v20, v24], [v35, v37]])
+}
+
+pub fn constant(x: [f64; 1]) -> ([f64; 2], [[f64; 1]; 2])
+{
+    let v3 = x[0] * 3.0;
+    ([-2.0, v3], [[0.0], [3.0]])
+}